@@ -1,10 +1,20 @@
+use crate::util::{open_file, read_file};
 use crate::ParseResult;
-use criterion::{black_box, criterion_group, BatchSize, Criterion};
+use criterion::{black_box, criterion_group, BatchSize, BenchmarkId, Criterion};
+use payment_processor::account::AccountOptions;
 use payment_processor::account_activity::AccountActivity;
-use payment_processor::processor::process_activities;
+use payment_processor::chronology::OutOfOrderPolicy;
+use payment_processor::processor::{process_activities, Reporting};
+use payment_processor::processors::csv::reader::CsvReader;
+use payment_processor::rejection::DiscardRejections;
+use payment_processor::sharded::process_activities_sharded;
 use payment_processor::transaction::TransactionID;
 use payment_processor::ClientID;
 use rust_decimal_macros::dec;
+use std::fs::File;
+use std::io::Read;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 
 fn bench_process_transactions(c: &mut Criterion) {
     let client_id_1 = ClientID(1);
@@ -31,10 +41,51 @@ fn bench_process_transactions(c: &mut Criterion) {
     c.bench_function("process_activities [dispute process]", move |b| {
         b.iter_batched(
             || transactions.clone().into_iter().map(Ok).collect::<ParseResult>(),
-            |transactions| process_activities(black_box(transactions.into_iter())),
+            |transactions| {
+                process_activities(black_box(transactions.into_iter()), &mut DiscardRejections)
+            },
             BatchSize::SmallInput,
         )
     });
 }
 
-criterion_group!(benches, bench_process_transactions);
+fn bench_process_sharded(c: &mut Criterion) {
+    let buffer = read_file!("activities_10K.csv");
+    let activities = CsvReader::try_new(buffer.as_slice())
+        .expect("Benchmark setup: unable to create csv reader")
+        .iter()
+        .filter_map(|record| record.value.ok())
+        .collect::<Vec<AccountActivity>>();
+
+    let mut group = c.benchmark_group("process_activities [10K]");
+    group.throughput(criterion::Throughput::Elements(activities.len() as u64));
+    group.bench_function("sequential", |b| {
+        b.iter_batched(
+            || activities.clone().into_iter().map(Ok).collect::<ParseResult>(),
+            |activities| {
+                process_activities(black_box(activities.into_iter()), &mut DiscardRejections)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    for shards in [2, 4, 8] {
+        let shards = NonZeroUsize::new(shards).expect("Benchmark setup: invalid shard count");
+        group.bench_with_input(BenchmarkId::new("sharded", shards), &shards, |b, &shards| {
+            b.iter_batched(
+                || activities.clone().into_iter().map(Ok).collect::<ParseResult>(),
+                |activities| process_activities_sharded(
+                    Vec::new(),
+                    AccountOptions::default(),
+                    OutOfOrderPolicy::default(),
+                    black_box(activities.into_iter()),
+                    shards,
+                    Reporting::new(&mut DiscardRejections),
+                ),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_process_transactions, bench_process_sharded);
@@ -1,12 +1,484 @@
-use crate::account_activity::AccountActivity;
-use crate::account_activity::AccountActivityError::{FailedDisputeCase, FailedTransaction, InvalidTransaction};
+use crate::account_activity::{AccountActivity, ActivityKind};
+use crate::account_activity::AccountActivityError::{
+    AccountLocked, AlreadyDisputed, DisputeConcluded, DisputeWindowExpired, DuplicateTransaction,
+    InsufficientFunds, NegativeAmount, NotDisputed, NotFrozen, NotLocked, TransactionFailed,
+    UnknownTransaction, WithdrawalNotDisputable,
+};
 use crate::account_activity::AccountActivityResult;
-use crate::transaction::{Transaction, TransactionID};
+use crate::admin::AdminAction;
+use crate::amount::AmountPolicy;
+use crate::ledger::{Bucket, Ledger};
+use crate::snapshot::{AccountSnapshot, AccountWriter, TransactionSnapshot};
+use crate::storage::{MemoryTransactionStore, Storage, StorageResult, TransactionStore};
+use crate::timestamp::Timestamp;
+use crate::transaction::{Transaction, TransactionID, TransactionKind};
+use crate::transfer::Transfer;
 use crate::ClientID;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use serde::ser::{Error as _, SerializeSeq, SerializeStruct};
+use serde::{Serialize, Serializer};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The lifecycle state of a recorded [transaction](Transaction).
+///
+/// Every transaction starts out as [`Processed`](TransactionState::Processed) and can only move
+/// forward along the following transitions:
+///
+/// ```text
+/// Processed ──dispute──▶ Disputed ──resolve─────▶ Resolved
+///                                 └──chargeback──▶ ChargedBack
+/// ```
+///
+/// [`Resolved`](TransactionState::Resolved) and [`ChargedBack`](TransactionState::ChargedBack) are
+/// final states. Any other step is rejected.
+///
+/// Transactions that fail, e.g. for lack of funds, are recorded as
+/// [`Failed`](TransactionState::Failed) and never take part in a dispute case. Neither do
+/// [`Evicted`](TransactionState::Evicted) transactions, whose dispute window has expired.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionState {
+    /// The transaction has been executed and is not subject to a dispute case.
+    Processed,
+
+    /// The transaction is disputed and its funds are held.
+    Disputed,
+
+    /// The dispute case was concluded in favor of the transaction and its funds were released.
+    Resolved,
+
+    /// The dispute case was concluded with a chargeback of the held funds.
+    ChargedBack,
+
+    /// The transaction has been rejected without moving any funds. Its ID is recorded to reject
+    /// duplicates, but it cannot be disputed.
+    Failed,
+
+    /// The dispute window of the transaction has expired and its record has been evicted. Only
+    /// its ID is kept to reject duplicates.
+    Evicted,
+}
+
+impl TransactionState {
+    /// Returns the state that follows this state when applying the dispute related `activity`, or
+    /// `None` if the step is not permitted in this state.
+    pub fn next(self, activity: &AccountActivity) -> Option<TransactionState> {
+        use TransactionState::{ChargedBack, Disputed, Processed, Resolved};
+        match (self, activity) {
+            (Processed, AccountActivity::Dispute(_)) => Some(Disputed),
+            (Disputed, AccountActivity::Resolve(_)) => Some(Resolved),
+            (Disputed, AccountActivity::Chargeback(_)) => Some(ChargedBack),
+            _ => None,
+        }
+    }
+}
+
+impl Display for TransactionState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            TransactionState::Processed => "processed",
+            TransactionState::Disputed => "disputed",
+            TransactionState::Resolved => "resolved",
+            TransactionState::ChargedBack => "charged back",
+            TransactionState::Failed => "failed",
+            TransactionState::Evicted => "evicted",
+        };
+        write!(f, "{}", state)
+    }
+}
+
+/// The balances of an [`Account`] as they are written to the output.
+#[derive(Debug, PartialEq, Clone, serde::Serialize)]
+pub struct AccountBalances {
+    #[serde(rename = "client")]
+    client_id: ClientID,
+
+    available: Decimal,
+
+    held: Decimal,
+
+    total: Decimal,
+
+    locked: bool,
+
+    /// The reason the account is locked for, if it is locked.
+    lock_reason: Option<String>,
+}
+
+impl AccountBalances {
+    pub fn client_id(&self) -> ClientID {
+        self.client_id
+    }
+
+    pub fn available(&self) -> Decimal {
+        self.available
+    }
+
+    pub fn held(&self) -> Decimal {
+        self.held
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.total
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn lock_reason(&self) -> Option<&str> {
+        self.lock_reason.as_deref()
+    }
+
+    /// Normalizes all balances to the precision of the given policy.
+    pub fn normalized(self, policy: &AmountPolicy) -> Self {
+        Self {
+            available: policy.normalize(self.available),
+            held: policy.normalize(self.held),
+            total: policy.normalize(self.total),
+            ..self
+        }
+    }
+}
+
+/// The balances and lock status of an [`Account`] at a single point in time, see
+/// [`ActivityEffect`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize)]
+pub struct Balances {
+    pub available: Decimal,
+    pub held: Decimal,
+    pub total: Decimal,
+    pub locked: bool,
+}
+
+/// Whether an activity has been applied to an account or ignored, see [`ActivityEffect`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectStatus {
+    /// The activity has been applied to the account.
+    Applied,
+
+    /// The activity is a dispute case step referencing a transaction that has never been
+    /// recorded and has been ignored, leaving the account unchanged.
+    UnknownTransaction,
+}
+
+/// A change of the lock status of an account caused by an activity.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockChange {
+    Locked,
+    Unlocked,
+}
+
+/// The effect of an activity that has been accepted by an [`Account`], as returned by
+/// [`Account::transaction`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ActivityEffect {
+    client_id: ClientID,
+    status: EffectStatus,
+    before: Balances,
+    after: Balances,
+}
+
+impl ActivityEffect {
+    /// Returns the client ID of the account the activity has been applied to.
+    pub fn client_id(&self) -> ClientID {
+        self.client_id
+    }
+
+    pub fn status(&self) -> EffectStatus {
+        self.status
+    }
+
+    pub fn is_ignored(&self) -> bool {
+        self.status != EffectStatus::Applied
+    }
+
+    /// Returns the balances of the account before the activity.
+    pub fn before(&self) -> Balances {
+        self.before
+    }
+
+    /// Returns the balances of the account after the activity.
+    pub fn after(&self) -> Balances {
+        self.after
+    }
+
+    /// Returns whether the activity locked or unlocked the account.
+    pub fn lock_change(&self) -> Option<LockChange> {
+        match (self.before.locked, self.after.locked) {
+            (false, true) => Some(LockChange::Locked),
+            (true, false) => Some(LockChange::Unlocked),
+            _ => None,
+        }
+    }
+}
+
+/// The cause of an [`AccountLock`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockCause {
+    /// The account was locked by a chargeback.
+    Chargeback,
+
+    /// The account was frozen by an administrative action.
+    Freeze,
+}
+
+/// The reason an [`Account`] is locked for and, for administrative locks, the operator who
+/// locked it.
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AccountLock {
+    cause: LockCause,
+
+    reason: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    operator: Option<String>,
+}
+
+impl AccountLock {
+    /// Creates the lock imposed by the chargeback of the given transaction.
+    pub fn chargeback(transaction_id: TransactionID) -> Self {
+        Self {
+            cause: LockCause::Chargeback,
+            reason: format!("chargeback of transaction {}", transaction_id),
+            operator: None,
+        }
+    }
+
+    /// Creates the lock imposed by the given freeze.
+    pub fn freeze(action: &AdminAction) -> Self {
+        Self {
+            cause: LockCause::Freeze,
+            reason: action.reason().to_string(),
+            operator: action.operator().map(str::to_string),
+        }
+    }
+
+    pub fn cause(&self) -> LockCause {
+        self.cause
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+}
+
+/// The policy for disputes of withdrawals.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WithdrawalDisputes {
+    /// The disputed amount is provisionally credited to the held funds of the client until the
+    /// dispute case is concluded:
+    ///
+    /// | Step       | Effect                                                        |
+    /// |------------|---------------------------------------------------------------|
+    /// | dispute    | held and total funds increase by the amount                   |
+    /// | resolve    | the withdrawal stands, the provisional credit is removed      |
+    /// | chargeback | the withdrawal is reversed, the held amount becomes available |
+    #[default]
+    ProvisionalCredit,
+
+    /// Disputes of withdrawals are rejected.
+    Reject,
+}
+
+/// The policy for activities on locked accounts.
+///
+/// By default, a locked account rejects every activity. The policy lists the kinds of activities
+/// a locked account accepts nonetheless, e.g. the conclusion of dispute cases that were opened
+/// before the account was locked. The incoming leg of a [transfer](ActivityKind::Transfer) is
+/// accepted alongside deposits, the outgoing leg alongside transfers.
+///
+/// [Unfreezing](ActivityKind::Unfreeze) and [unlocking](ActivityKind::Unlock) a locked account is
+/// always accepted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LockedAccountPolicy {
+    /// The accepted activity kinds, one bit per kind.
+    accepted: u16,
+}
+
+impl LockedAccountPolicy {
+    /// Returns a policy that additionally accepts activities of the given kind.
+    pub fn accept(mut self, kind: ActivityKind) -> Self {
+        self.accepted |= Self::bit(kind);
+        self
+    }
+
+    /// Returns whether a locked account accepts activities of the given kind.
+    pub fn accepts(&self, kind: ActivityKind) -> bool {
+        matches!(kind, ActivityKind::Unfreeze | ActivityKind::Unlock)
+            || self.accepted & Self::bit(kind) != 0
+    }
+
+    fn bit(kind: ActivityKind) -> u16 {
+        1 << kind as u16
+    }
+}
+
+impl FromIterator<ActivityKind> for LockedAccountPolicy {
+    fn from_iter<I: IntoIterator<Item=ActivityKind>>(kinds: I) -> Self {
+        kinds.into_iter().fold(Self::default(), Self::accept)
+    }
+}
+
+/// Options that control the behavior of an [`Account`].
+#[derive(Debug, Default, Clone)]
+pub struct AccountOptions {
+    ledger: bool,
+    withdrawal_disputes: WithdrawalDisputes,
+    locked_accounts: LockedAccountPolicy,
+    storage: Option<Arc<dyn Storage>>,
+    dispute_window: Option<Duration>,
+    evict_expired: bool,
+    eviction_delay: Duration,
+}
+
+impl AccountOptions {
+    /// Sets whether the account records its balance movements in a [`Ledger`].
+    pub fn with_ledger(mut self, enabled: bool) -> Self {
+        self.ledger = enabled;
+        self
+    }
+
+    /// Sets the policy for disputes of withdrawals.
+    pub fn with_withdrawal_disputes(mut self, policy: WithdrawalDisputes) -> Self {
+        self.withdrawal_disputes = policy;
+        self
+    }
+
+    /// Sets the policy for activities on locked accounts.
+    pub fn with_locked_accounts(mut self, policy: LockedAccountPolicy) -> Self {
+        self.locked_accounts = policy;
+        self
+    }
+
+    /// Sets the storage of the transaction record. The record is held in memory by default.
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Sets the period after a transaction within which the transaction can be disputed. By
+    /// default, transactions can be disputed at any time.
+    ///
+    /// The window only applies to transactions with a timestamp. A dispute takes place at its own
+    /// timestamp or, if it has none, at the latest timestamp of all activities of the account.
+    pub fn with_dispute_window(mut self, window: Duration) -> Self {
+        self.dispute_window = Some(window);
+        self
+    }
+
+    /// Sets whether the records of transactions are evicted once their dispute window has
+    /// expired. Evicted records are removed from the [store](TransactionStore::evict), which only
+    /// keeps the ID of the transaction to reject duplicates. Has no effect without a
+    /// [dispute window](Self::with_dispute_window).
+    ///
+    /// Windows expire relative to the latest timestamp of all activities of the account. A
+    /// dispute that appears in the input after later activities only finds its transaction if it
+    /// precedes them by no more than the [eviction delay](Self::with_eviction_delay).
+    pub fn with_expired_eviction(mut self, enabled: bool) -> Self {
+        self.evict_expired = enabled;
+        self
+    }
+
+    /// Sets the period by which the eviction of a record is delayed after its dispute window has
+    /// expired. Defaults to zero.
+    ///
+    /// Disputes that precede the latest timestamp of the account by up to `delay` are judged by
+    /// the record of their transaction, so the delay should cover the tolerance of the
+    /// [`OutOfOrderPolicy`](crate::chronology::OutOfOrderPolicy) the input is processed with.
+    pub fn with_eviction_delay(mut self, delay: Duration) -> Self {
+        self.eviction_delay = delay;
+        self
+    }
+
+    /// Returns the period after a transaction after which its record is evicted, if expired
+    /// transactions are evicted.
+    fn eviction_window(&self) -> Option<Duration> {
+        self.dispute_window
+            .filter(|_| self.evict_expired)
+            .map(|window| window.saturating_add(self.eviction_delay))
+    }
+
+    /// Returns the storage of the transaction record, unless it is held in memory.
+    pub(crate) fn storage(&self) -> Option<&dyn Storage> {
+        self.storage.as_deref()
+    }
+
+    fn open_store(&self, client_id: ClientID) -> Box<dyn TransactionStore> {
+        match &self.storage {
+            Some(storage) => storage.open(client_id),
+            None => Box::new(MemoryTransactionStore::default()),
+        }
+    }
+
+    fn has_same_storage(&self, other: &AccountOptions) -> bool {
+        match (&self.storage, &other.storage) {
+            (Some(storage), Some(other)) => Arc::ptr_eq(storage, other),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A recorded transaction alongside the state of its dispute lifecycle.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TransactionRecord {
+    kind: TransactionKind,
+    amount: Decimal,
+    state: TransactionState,
+    timestamp: Option<Timestamp>,
+}
+
+impl TransactionRecord {
+    /// Creates the record of a newly processed transaction.
+    pub fn new(kind: TransactionKind, amount: Decimal) -> Self {
+        Self { kind, amount, state: TransactionState::Processed, timestamp: None }
+    }
+
+    /// Creates the tombstone reported for an evicted transaction, whose record has been removed.
+    pub fn evicted() -> Self {
+        Self::new(TransactionKind::default(), Decimal::ZERO).with_state(TransactionState::Evicted)
+    }
+
+    /// Returns a copy of the record with its state replaced.
+    pub fn with_state(self, state: TransactionState) -> Self {
+        Self { state, ..self }
+    }
+
+    /// Returns a copy of the record with the point in time of the transaction replaced.
+    pub fn with_timestamp(self, timestamp: Option<Timestamp>) -> Self {
+        Self { timestamp, ..self }
+    }
+
+    pub fn kind(&self) -> TransactionKind {
+        self.kind
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn state(&self) -> TransactionState {
+        self.state
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
 
 /// An abstraction over the balances of a client.
 ///
@@ -23,6 +495,9 @@ use std::collections::{HashMap, HashSet};
 /// | held      | Temporarily unavailable due to pending transactions, deposits, or disputes |
 /// | total     | Full balance in the account, including both the available and held funds   |
 ///
+/// If enabled via [`AccountOptions::with_ledger`], every change of these balances is recorded in
+/// a [`Ledger`].
+///
 /// # Security
 ///
 /// ## Transactions
@@ -35,16 +510,36 @@ use std::collections::{HashMap, HashSet};
 ///
 /// A [dispute case](crate::dispute::DisputeCase) must follow all required steps in the process.
 /// [Resolutions] and [chargebacks] are only processed if the corresponding transaction has been
-/// properly disputed beforehand.
+/// properly disputed beforehand. Steps that violate the lifecycle described by
+/// [`TransactionState`] are rejected with an [`AccountActivityError`] describing the violation.
+///
+/// Disputes for non-existent transactions are ignored, as reported by the [`ActivityEffect`] of the
+/// dispute.
+///
+/// If a [dispute window](AccountOptions::with_dispute_window) is configured, transactions can only
+/// be disputed within the window. The records of transactions whose window has expired can be
+/// [evicted](AccountOptions::with_expired_eviction); their IDs are kept to reject duplicates.
 ///
-/// Disputes for non-existent transactions are silently ignored.
+/// A disputed deposit holds the deposited funds, whereas a disputed withdrawal is handled as
+/// configured by the [`WithdrawalDisputes`] policy.
+///
+/// ## Locks
+///
+/// A chargeback locks the account, as does a [freeze] by support staff. A locked account rejects
+/// all activities except for the administrative actions lifting the lock and the activities
+/// accepted by the [`LockedAccountPolicy`]: a frozen account can be [unfrozen], whereas any lock
+/// can be lifted by [unlocking] the account. The reason of the lock is recorded in the account,
+/// see [`AccountLock`].
 ///
 /// [transactions]: crate::transaction::Transaction
 /// [Resolutions]: crate::account_activity::AccountActivity::Resolve
 /// [chargebacks]: crate::account_activity::AccountActivity::Chargeback
-#[derive(Debug, PartialEq, serde::Serialize)]
+/// [freeze]: crate::account_activity::AccountActivity::Freeze
+/// [unfrozen]: crate::account_activity::AccountActivity::Unfreeze
+/// [unlocking]: crate::account_activity::AccountActivity::Unlock
+/// [`AccountActivityError`]: crate::account_activity::AccountActivityError
+#[derive(Debug)]
 pub struct Account {
-    #[serde(rename = "client")]
     client_id: ClientID,
 
     available: Decimal,
@@ -53,28 +548,81 @@ pub struct Account {
 
     total: Decimal,
 
-    locked: bool,
+    lock: Option<AccountLock>,
+
+    transaction_record: Box<dyn TransactionStore>,
+
+    /// The latest point in time of all activities applied to the account.
+    clock: Option<Timestamp>,
+
+    /// The recorded transactions that are evicted once their dispute window has expired, ordered
+    /// by the point in time of the transaction.
+    expiries: BinaryHeap<Reverse<(Timestamp, TransactionID)>>,
 
-    #[serde(skip)]
-    dispute_cases: HashSet<TransactionID>,
+    ledger: Option<Ledger>,
 
-    #[serde(skip)]
-    transaction_record: HashMap<TransactionID, Decimal>,
+    options: AccountOptions,
 }
 
 impl Account {
     pub fn new(client_id: ClientID) -> Self {
+        Self::with_options(client_id, AccountOptions::default())
+    }
+
+    /// Creates an account that behaves as configured by `options`.
+    pub fn with_options(client_id: ClientID, options: AccountOptions) -> Self {
         Self {
             client_id,
             held: dec!(0.0),
             total: dec!(0.0),
             available: dec!(0.0),
-            locked: false,
-            dispute_cases: HashSet::new(),
-            transaction_record: HashMap::new(),
+            lock: None,
+            transaction_record: options.open_store(client_id),
+            clock: None,
+            expiries: BinaryHeap::new(),
+            ledger: options.ledger.then(Ledger::default),
+            options,
         }
     }
 
+    /// Applies the given options to the account.
+    ///
+    /// Enabling the ledger of an account that already records its movements keeps the recorded
+    /// movements, disabling it discards them. Changing the storage moves the transaction record
+    /// to the new storage. Enabling the eviction of expired transactions evicts the records of
+    /// all transactions whose dispute window has already expired.
+    pub fn configure(&mut self, options: AccountOptions) -> StorageResult<()> {
+        match (options.ledger, &self.ledger) {
+            (true, None) => self.ledger = Some(Ledger::default()),
+            (false, Some(_)) => self.ledger = None,
+            _ => {}
+        }
+        if !options.has_same_storage(&self.options) {
+            let mut store = options.open_store(self.client_id);
+            self.transaction_record.for_each_record(&mut |transaction_id, record| {
+                if record.state == TransactionState::Evicted {
+                    store.evict(transaction_id)
+                } else {
+                    store.insert(transaction_id, record).map(|_| ())
+                }
+            })?;
+            self.transaction_record = store;
+        }
+        let schedule = options.eviction_window().is_some();
+        self.options = options;
+        self.expiries.clear();
+        if schedule {
+            let expiries = &mut self.expiries;
+            self.transaction_record.for_each_record(&mut |transaction_id, record| {
+                if let Some(timestamp) = record.timestamp {
+                    expiries.push(Reverse((timestamp, transaction_id)));
+                }
+                Ok(())
+            })?;
+        }
+        self.evict_expired()
+    }
+
     pub fn client_id(&self) -> ClientID {
         self.client_id
     }
@@ -92,139 +640,544 @@ impl Account {
     }
 
     pub fn is_locked(&self) -> bool {
-        self.locked
+        self.lock.is_some()
     }
 
-    fn lock(&mut self) {
-        self.locked = true;
+    /// Returns the reason the account is locked for, if it is locked.
+    pub fn lock(&self) -> Option<&AccountLock> {
+        self.lock.as_ref()
+    }
+
+    /// Returns the balances of the account as they are written to the output.
+    pub fn balances(&self) -> AccountBalances {
+        AccountBalances {
+            client_id: self.client_id,
+            available: self.available,
+            held: self.held,
+            total: self.total,
+            locked: self.is_locked(),
+            lock_reason: self.lock.as_ref().map(|lock| lock.reason.clone()),
+        }
+    }
+
+    /// Returns the current balances of the account, as recorded by an [`ActivityEffect`].
+    fn current_balances(&self) -> Balances {
+        Balances {
+            available: self.available,
+            held: self.held,
+            total: self.total,
+            locked: self.is_locked(),
+        }
+    }
+
+    /// Returns the recorded balance movements, if the ledger is enabled.
+    pub fn ledger(&self) -> Option<&Ledger> {
+        self.ledger.as_ref()
+    }
+
+    /// Records a movement of `amount` from `debit` to `credit` in the ledger, if it is enabled.
+    fn post(&mut self, debit: Bucket, credit: Bucket, amount: Decimal) {
+        let balances = self.balances();
+        if let Some(ledger) = &mut self.ledger {
+            ledger.post(&balances, debit, credit, amount);
+        }
     }
 
     fn deposit(&mut self, amount: Decimal) -> AccountActivityResult<()> {
         if amount.is_sign_negative() {
-            Err(InvalidTransaction("deposit amount must be a positive number".into()))
+            Err(NegativeAmount(amount))
         } else {
             self.available += amount;
             self.total += amount;
+            self.post(Bucket::External, Bucket::Available, amount);
             Ok(())
         }
     }
 
     fn withdraw(&mut self, amount: Decimal) -> AccountActivityResult<()> {
         if amount.is_sign_negative() {
-            Err(InvalidTransaction("withdrawal amount must be a positive number".into()))
+            Err(NegativeAmount(amount))
         } else if amount > self.available {
-            Err(FailedTransaction("withdrawal failed because of insufficient funds".into()))
+            Err(InsufficientFunds { requested: amount, available: self.available })
         } else {
             self.available -= amount;
             self.total -= amount;
+            self.post(Bucket::Available, Bucket::External, amount);
             Ok(())
         }
     }
 
     fn hold(&mut self, amount: Decimal) -> AccountActivityResult<()> {
         if amount.is_sign_negative() {
-            Err(InvalidTransaction("hold amount must be a positive number".into()))
+            Err(NegativeAmount(amount))
         } else {
             self.available -= amount;
             self.held += amount;
+            self.post(Bucket::Available, Bucket::Held, amount);
             Ok(())
         }
     }
 
     fn release(&mut self, amount: Decimal) -> AccountActivityResult<()> {
         if amount.is_sign_negative() {
-            Err(InvalidTransaction("release amount must be a positive number".into()))
+            Err(NegativeAmount(amount))
         } else {
             self.held -= amount;
             self.available += amount;
+            self.post(Bucket::Held, Bucket::Available, amount);
+            Ok(())
+        }
+    }
+
+    /// Provisionally credits `amount` to the held funds, e.g. for a disputed withdrawal.
+    fn credit_held(&mut self, amount: Decimal) -> AccountActivityResult<()> {
+        if amount.is_sign_negative() {
+            Err(NegativeAmount(amount))
+        } else {
+            self.held += amount;
+            self.total += amount;
+            self.post(Bucket::External, Bucket::Held, amount);
             Ok(())
         }
     }
 
     fn charge(&mut self, amount: Decimal) -> AccountActivityResult<()> {
         if amount.is_sign_negative() {
-            Err(InvalidTransaction("chargeback amount must be a positive number".into()))
+            Err(NegativeAmount(amount))
         } else {
             self.held -= amount;
             self.total -= amount;
+            self.post(Bucket::Held, Bucket::External, amount);
             Ok(())
         }
     }
 
-    fn initiate_dispute(&mut self, transaction_id: TransactionID) -> AccountActivityResult<()> {
-        match self.dispute_cases.contains(&transaction_id) {
-            true => Err(FailedDisputeCase("transaction already disputed".into())),
-            false => match self.transaction_record.get(&transaction_id) {
-                None => Ok(()),
-                Some(&amount) => {
-                    self.dispute_cases.insert(transaction_id);
-                    self.hold(amount)
-                }
-            }
+    /// Advances the clock of the account to `timestamp`, unless it is past `timestamp` already,
+    /// and evicts the records of transactions whose dispute window has expired since.
+    fn advance_clock(&mut self, timestamp: Timestamp) -> StorageResult<()> {
+        if self.clock >= Some(timestamp) {
+            return Ok(());
+        }
+        self.clock = Some(timestamp);
+        self.evict_expired()
+    }
+
+    /// Returns whether the dispute window of the transaction has expired at the point in time
+    /// `now`. Transactions without a timestamp never expire.
+    fn is_expired(&self, record: TransactionRecord, now: Option<Timestamp>) -> bool {
+        match (record.timestamp, self.options.dispute_window, now) {
+            (Some(timestamp), Some(window), Some(now)) => timestamp.saturating_add(window) < now,
+            _ => false,
         }
     }
 
-    fn resolve_dispute(&mut self, transaction_id: &TransactionID) -> AccountActivityResult<()> {
-        if let Some(&amount) = self.transaction_record.get(transaction_id) {
-            self.release(amount)?;
-            self.dispute_cases.remove(transaction_id);
+    /// Returns whether the record of the transaction is due for eviction, i.e. whether its dispute
+    /// window has expired longer than the eviction delay ago.
+    fn is_evictable(&self, record: TransactionRecord) -> bool {
+        match (record.timestamp, self.options.eviction_window(), self.clock) {
+            (Some(timestamp), Some(window), Some(now)) => timestamp.saturating_add(window) < now,
+            _ => false,
+        }
+    }
+
+    /// Schedules the record of a transaction for eviction once its dispute window has expired.
+    fn schedule_eviction(&mut self, transaction_id: TransactionID, record: TransactionRecord) {
+        if let (Some(timestamp), Some(_)) = (record.timestamp, self.options.eviction_window()) {
+            self.expiries.push(Reverse((timestamp, transaction_id)));
+        }
+    }
+
+    fn evict_expired(&mut self) -> StorageResult<()> {
+        while let Some(&Reverse((_, transaction_id))) = self.expiries.peek() {
+            let record = self.transaction_record.get(transaction_id)?;
+            let Some(record) = record.filter(|record| record.state != TransactionState::Evicted)
+            else {
+                // Evicted already, e.g. after having been scheduled again
+                self.expiries.pop();
+                continue;
+            };
+            if !self.is_evictable(record) {
+                break;
+            }
+            self.expiries.pop();
+            // Disputed transactions are scheduled again once their dispute case is concluded
+            if record.state != TransactionState::Disputed {
+                self.transaction_record.evict(transaction_id)?;
+            }
         }
         Ok(())
     }
 
-    fn issue_chargeback(&mut self, transaction_id: &TransactionID) -> AccountActivityResult<()> {
-        if let Some(&amount) = self.transaction_record.get(transaction_id) {
-            self.charge(amount)?;
-            self.dispute_cases.remove(transaction_id);
-            self.lock();
+    /// Advances the dispute lifecycle of the transaction referenced by `activity` and applies
+    /// `effect` to the transaction's record.
+    ///
+    /// The state is only updated if `effect` succeeds.
+    fn advance_dispute_case<F>(
+        &mut self,
+        activity: &AccountActivity,
+        effect: F,
+    ) -> AccountActivityResult<()>
+    where
+        F: FnOnce(&mut Self, TransactionRecord) -> AccountActivityResult<()>,
+    {
+        let transaction_id = activity.transaction_id();
+        let Some(record) = self.transaction_record.get(transaction_id)? else {
+            return Err(UnknownTransaction(transaction_id));
+        };
+        let state = record.state.next(activity).ok_or(match (record.state, activity) {
+            (TransactionState::Processed, _) => NotDisputed(transaction_id),
+            (TransactionState::Disputed, _) => AlreadyDisputed(transaction_id),
+            (TransactionState::Failed, _) => TransactionFailed(transaction_id),
+            // Evicted transactions are never disputed
+            (TransactionState::Evicted, AccountActivity::Dispute(_)) => {
+                DisputeWindowExpired(transaction_id)
+            }
+            (TransactionState::Evicted, _) => NotDisputed(transaction_id),
+            (state, _) => DisputeConcluded { transaction_id, state },
+        })?;
+        effect(self, record)?;
+        let record = record.with_state(state);
+        self.transaction_record.update(transaction_id, record)?;
+        if state != TransactionState::Disputed {
+            self.schedule_eviction(transaction_id, record);
         }
         Ok(())
     }
 
-    fn record_transaction(&mut self, transaction: Transaction) -> AccountActivityResult<()> {
-        match self.transaction_record.entry(transaction.id()) {
-            Entry::Occupied(_) => Err(FailedTransaction("transaction already recorded".into())),
-            Entry::Vacant(entry) => {
-                entry.insert(transaction.amount());
+    fn initiate_dispute(&mut self, activity: &AccountActivity) -> AccountActivityResult<()> {
+        let disputed_at = activity.timestamp().or(self.clock);
+        self.advance_dispute_case(activity, |account, record| {
+            if account.is_expired(record, disputed_at) {
+                return Err(DisputeWindowExpired(activity.transaction_id()));
+            }
+            match record.kind {
+                TransactionKind::Deposit => account.hold(record.amount),
+                TransactionKind::Withdrawal => match account.options.withdrawal_disputes {
+                    WithdrawalDisputes::ProvisionalCredit => account.credit_held(record.amount),
+                    WithdrawalDisputes::Reject => {
+                        Err(WithdrawalNotDisputable(activity.transaction_id()))
+                    }
+                },
+            }
+        })
+    }
+
+    fn resolve_dispute(&mut self, activity: &AccountActivity) -> AccountActivityResult<()> {
+        self.advance_dispute_case(activity, |account, record| match record.kind {
+            TransactionKind::Deposit => account.release(record.amount),
+            TransactionKind::Withdrawal => account.charge(record.amount),
+        })
+    }
+
+    fn issue_chargeback(&mut self, activity: &AccountActivity) -> AccountActivityResult<()> {
+        self.advance_dispute_case(activity, |account, record| {
+            match record.kind {
+                TransactionKind::Deposit => account.charge(record.amount)?,
+                TransactionKind::Withdrawal => account.release(record.amount)?,
+            }
+            account.lock = Some(AccountLock::chargeback(activity.transaction_id()));
+            Ok(())
+        })
+    }
+
+    /// Freezes the account. A frozen account may be frozen again, e.g. to update the reason, but
+    /// a lock of another cause is never replaced, as it could then be lifted by an unfreeze.
+    fn freeze(&mut self, action: &AdminAction) -> AccountActivityResult<()> {
+        match &self.lock {
+            Some(lock) if lock.cause != LockCause::Freeze => Err(AccountLocked(self.client_id)),
+            _ => {
+                self.lock = Some(AccountLock::freeze(action));
                 Ok(())
             }
         }
     }
 
-    /// Process an account activity, which could either be a transaction or a dispute activity.
-    pub fn transaction(&mut self, activity: AccountActivity) -> AccountActivityResult<()> {
-        if self.is_locked() {
-            return Err(FailedTransaction("account locked".into()));
+    fn unfreeze(&mut self) -> AccountActivityResult<()> {
+        match &self.lock {
+            None => Err(NotLocked(self.client_id)),
+            Some(lock) if lock.cause != LockCause::Freeze => Err(NotFrozen(self.client_id)),
+            Some(_) => self.unlock(),
+        }
+    }
+
+    fn unlock(&mut self) -> AccountActivityResult<()> {
+        self.lock.take().map(|_| ()).ok_or(NotLocked(self.client_id))
+    }
+
+    /// Records `transaction` and moves its funds with `execute`. The record of a transaction
+    /// that fails is marked as [failed](TransactionState::Failed).
+    fn record_transaction<F>(
+        &mut self,
+        kind: TransactionKind,
+        transaction: Transaction,
+        execute: F,
+    ) -> AccountActivityResult<()>
+    where
+        F: FnOnce(&mut Self, Decimal) -> AccountActivityResult<()>,
+    {
+        let record = TransactionRecord::new(kind, transaction.amount())
+            .with_timestamp(transaction.timestamp());
+        if !self.transaction_record.insert(transaction.id(), record)? {
+            return Err(DuplicateTransaction(transaction.id()));
+        }
+        self.schedule_eviction(transaction.id(), record);
+        let result = execute(self, transaction.amount());
+        if result.is_err() {
+            let record = record.with_state(TransactionState::Failed);
+            self.transaction_record.update(transaction.id(), record)?;
         }
-        match activity {
+        result
+    }
+
+    /// Calls `visit` with the IDs of all recorded transactions in ascending order, including
+    /// evicted transactions. Stops at the first error returned by `visit` and returns it.
+    pub fn for_each_transaction_id<F>(&self, mut visit: F) -> StorageResult<()>
+    where
+        F: FnMut(TransactionID) -> StorageResult<()>,
+    {
+        self.transaction_record.for_each_record(&mut |transaction_id, _| visit(transaction_id))
+    }
+
+    /// Returns whether `other` records the same transactions in the same states.
+    fn has_same_transactions(&self, other: &Account) -> StorageResult<bool> {
+        let mut same = true;
+        let mut count = 0_usize;
+        self.transaction_record.for_each_record(&mut |transaction_id, record| {
+            same &= other.transaction_record.get(transaction_id)? == Some(record);
+            count += 1;
+            Ok(())
+        })?;
+        let mut other_count = 0_usize;
+        other.transaction_record.for_each_record(&mut |_, _| {
+            other_count += 1;
+            Ok(())
+        })?;
+        Ok(same && count == other_count)
+    }
+
+    /// Returns the lifecycle state of the transaction with the given ID, if it has been recorded.
+    pub fn transaction_state(
+        &self,
+        transaction_id: TransactionID,
+    ) -> StorageResult<Option<TransactionState>> {
+        let record = self.transaction_record.get(transaction_id)?;
+        Ok(record.map(|record| record.state))
+    }
+
+    /// Checks whether the account accepts the incoming leg of `transfer`, without modifying it.
+    pub fn check_incoming_transfer(&self, transfer: &Transfer) -> AccountActivityResult<()> {
+        if self.is_locked() && !self.options.locked_accounts.accepts(ActivityKind::Deposit) {
+            Err(AccountLocked(self.client_id))
+        } else if self.transaction_record.contains(transfer.id())? {
+            Err(DuplicateTransaction(transfer.id()))
+        } else if transfer.amount().is_sign_negative() {
+            Err(NegativeAmount(transfer.amount()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies the outgoing leg of `transfer`, withdrawing the transferred funds.
+    ///
+    /// The transfer is recorded like a withdrawal, so a transfer that fails is recorded as
+    /// [failed](TransactionState::Failed) without moving any funds. See
+    /// [`Account::receive_transfer`] for the incoming leg.
+    fn send_transfer(&mut self, transfer: Transfer) -> AccountActivityResult<()> {
+        let transaction = transfer.transaction(self.client_id);
+        self.record_transaction(TransactionKind::Withdrawal, transaction, Self::withdraw)
+    }
+
+    /// Applies the incoming leg of `transfer`, depositing the transferred funds.
+    ///
+    /// The transfer is recorded like a deposit, so disputes of the transfer hold the received
+    /// funds. See [`AccountActivity::Transfer`] for the outgoing leg.
+    pub fn receive_transfer(
+        &mut self,
+        transfer: Transfer,
+    ) -> AccountActivityResult<ActivityEffect> {
+        if let Some(timestamp) = transfer.timestamp() {
+            self.advance_clock(timestamp)?;
+        }
+        self.check_incoming_transfer(&transfer)?;
+        let before = self.current_balances();
+        if let Some(ledger) = &mut self.ledger {
+            ledger.begin(&AccountActivity::Transfer(transfer));
+        }
+        let transaction = transfer.transaction(self.client_id);
+        self.record_transaction(TransactionKind::Deposit, transaction, Self::deposit)?;
+        Ok(self.effect(EffectStatus::Applied, before))
+    }
+
+    fn effect(&self, status: EffectStatus, before: Balances) -> ActivityEffect {
+        ActivityEffect { client_id: self.client_id, status, before, after: self.current_balances() }
+    }
+
+    /// Process an account activity, which could either be a transaction, a dispute activity or an
+    /// administrative action.
+    ///
+    /// Dispute case steps referencing unknown transactions are ignored, every other failure is
+    /// reported as an [`AccountActivityError`](crate::account_activity::AccountActivityError).
+    /// Returns the [effect](ActivityEffect) of an accepted activity on the account.
+    pub fn transaction(
+        &mut self,
+        activity: AccountActivity,
+    ) -> AccountActivityResult<ActivityEffect> {
+        if let Some(timestamp) = activity.timestamp() {
+            self.advance_clock(timestamp)?;
+        }
+        if self.is_locked() && !self.options.locked_accounts.accepts(activity.kind()) {
+            return Err(AccountLocked(self.client_id));
+        }
+        let before = self.current_balances();
+        if let Some(ledger) = &mut self.ledger {
+            ledger.begin(&activity);
+        }
+        let result = match activity {
             AccountActivity::Deposit(transaction) => {
-                self.record_transaction(transaction)?;
-                self.deposit(transaction.amount())
+                self.record_transaction(TransactionKind::Deposit, transaction, Self::deposit)
             }
             AccountActivity::Withdrawal(transaction) => {
-                self.record_transaction(transaction)?;
-                self.withdraw(transaction.amount())
-            }
-            AccountActivity::Dispute(dispute_case) => {
-                self.initiate_dispute(dispute_case.id())
-            }
-            AccountActivity::Resolve(dispute_case) => {
-                self.resolve_dispute(&dispute_case.id())
-            }
-            AccountActivity::Chargeback(dispute_case) => {
-                self.issue_chargeback(&dispute_case.id())
+                self.record_transaction(TransactionKind::Withdrawal, transaction, Self::withdraw)
             }
+            AccountActivity::Transfer(transfer) => self.send_transfer(transfer),
+            AccountActivity::Dispute(_) => self.initiate_dispute(&activity),
+            AccountActivity::Resolve(_) => self.resolve_dispute(&activity),
+            AccountActivity::Chargeback(_) => self.issue_chargeback(&activity),
+            AccountActivity::Freeze(action) => self.freeze(&action),
+            AccountActivity::Unfreeze(_) => self.unfreeze(),
+            AccountActivity::Unlock(_) => self.unlock(),
+        };
+        let status = match result {
+            Ok(()) => EffectStatus::Applied,
+            Err(UnknownTransaction(_)) => EffectStatus::UnknownTransaction,
+            Err(err) => return Err(err),
+        };
+        Ok(self.effect(status, before))
+    }
+}
+
+/// Accounts are equal if their balances, locks, ledgers and transaction records are equal.
+impl PartialEq for Account {
+    fn eq(&self, other: &Self) -> bool {
+        self.balances() == other.balances()
+            && self.lock == other.lock
+            && self.ledger == other.ledger
+            && self.has_same_transactions(other).unwrap_or(false)
+    }
+}
+
+impl Serialize for AccountWriter<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let account = self.account;
+        let mut snapshot = serializer.serialize_struct("AccountSnapshot", 9)?;
+        snapshot.serialize_field("client", &account.client_id)?;
+        snapshot.serialize_field("available", &account.available)?;
+        snapshot.serialize_field("held", &account.held)?;
+        snapshot.serialize_field("total", &account.total)?;
+        snapshot.serialize_field("locked", &account.is_locked())?;
+        match &account.lock {
+            Some(lock) => snapshot.serialize_field("lock", lock)?,
+            None => snapshot.skip_field("lock")?,
+        }
+        snapshot.serialize_field("transactions", &RecordWriter { writer: self, evicted: false })?;
+        match &account.clock {
+            Some(clock) => snapshot.serialize_field("clock", clock)?,
+            None => snapshot.skip_field("clock")?,
+        }
+        snapshot.serialize_field("evicted", &RecordWriter { writer: self, evicted: true })?;
+        snapshot.end()
+    }
+}
+
+/// Serializes the transaction record of an account as a sequence, streaming it from the store.
+/// The sequence either holds the records of all transactions that have not been evicted as
+/// [`TransactionSnapshot`]s or the IDs of all evicted transactions.
+struct RecordWriter<'a> {
+    writer: &'a AccountWriter<'a>,
+    evicted: bool,
+}
+
+impl Serialize for RecordWriter<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut sequence = serializer.serialize_seq(None)?;
+        let mut error = None;
+        let result = self.writer.account.transaction_record.for_each_record(&mut |tx, record| {
+            let result = match (record.state == TransactionState::Evicted, self.evicted) {
+                (true, true) => sequence.serialize_element(&tx),
+                (false, false) => sequence.serialize_element(&TransactionSnapshot {
+                    tx,
+                    kind: record.kind,
+                    amount: record.amount,
+                    state: record.state,
+                    timestamp: record.timestamp,
+                }),
+                _ => Ok(()),
+            };
+            result.map_err(|err| {
+                error = Some(err);
+                io::Error::other("failed to serialize transaction record")
+            })
+        });
+        if let Some(err) = error {
+            return Err(err);
+        }
+        if let Err(err) = result {
+            let message = err.to_string();
+            self.writer.failure.replace(Some(err));
+            return Err(S::Error::custom(message));
+        }
+        sequence.end()
+    }
+}
+
+impl From<AccountSnapshot> for Account {
+    fn from(snapshot: AccountSnapshot) -> Self {
+        // Snapshots of version 2 and earlier only record whether the account is locked, which was
+        // only ever caused by chargebacks
+        let lock = match (snapshot.lock, snapshot.locked) {
+            (Some(lock), _) => Some(lock),
+            (None, true) => Some(AccountLock {
+                cause: LockCause::Chargeback,
+                reason: "chargeback".to_string(),
+                operator: None,
+            }),
+            (None, false) => None,
+        };
+        let transaction_record = snapshot.transactions
+            .into_iter()
+            .map(|transaction| (transaction.tx, TransactionRecord {
+                kind: transaction.kind,
+                amount: transaction.amount,
+                state: transaction.state,
+                timestamp: transaction.timestamp,
+            }))
+            .chain(snapshot.evicted.into_iter().map(|tx| (tx, TransactionRecord::evicted())))
+            .collect::<MemoryTransactionStore>();
+        Self {
+            client_id: snapshot.client,
+            available: snapshot.available,
+            held: snapshot.held,
+            total: snapshot.total,
+            lock,
+            transaction_record: Box::new(transaction_record),
+            clock: snapshot.clock,
+            expiries: BinaryHeap::new(),
+            ledger: None,
+            options: AccountOptions::default(),
         }
     }
 }
 
 #[cfg(test)]
 pub mod test_utils {
-    use super::Account;
+    use super::{Account, AccountLock, AccountOptions};
+    use crate::account_activity::AccountActivity;
+    use crate::storage::MemoryTransactionStore;
+    use crate::transaction::TransactionID;
     use crate::ClientID;
     use rust_decimal::Decimal;
     use rust_decimal_macros::dec;
-    use std::collections::{HashMap, HashSet};
+    use std::collections::BinaryHeap;
 
     pub enum LockStatus {
         Locked,
@@ -244,14 +1197,28 @@ pub mod test_utils {
                 held,
                 available,
                 total,
-                locked: match lock_status {
-                    LockStatus::Locked => true,
-                    LockStatus::Unlocked => false,
+                lock: match lock_status {
+                    LockStatus::Locked => Some(AccountLock::chargeback(TransactionID::default())),
+                    LockStatus::Unlocked => None,
                 },
-                dispute_cases: HashSet::new(),
-                transaction_record: HashMap::new(),
+                transaction_record: Box::new(MemoryTransactionStore::default()),
+                clock: None,
+                expiries: BinaryHeap::new(),
+                ledger: None,
+                options: AccountOptions::default(),
             }
         }
+
+        /// Applies `activities` in order, failing the test if any of them is rejected.
+        pub fn with_activities(
+            mut self,
+            activities: impl IntoIterator<Item=AccountActivity>,
+        ) -> Self {
+            for activity in activities {
+                self.transaction(activity).expect("Test setup: activity failed");
+            }
+            self
+        }
     }
 
     impl Default for Account {
@@ -269,11 +1236,17 @@ pub mod test_utils {
 
 #[cfg(test)]
 mod test_account_activities {
-    use super::Account;
+    use super::test_utils::LockStatus;
+    use super::{Account, AccountLock, EffectStatus, LockChange, TransactionRecord};
     use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{
+        AccountLocked, DuplicateTransaction, Storage,
+    };
+    use crate::storage::{RecordVisitor, StorageResult, TransactionStore};
     use crate::transaction::TransactionID;
     use crate::ClientID;
     use rust_decimal_macros::dec;
+    use std::io;
 
     #[test]
     fn transactions_with_same_id_are_only_processed_once() {
@@ -293,7 +1266,8 @@ mod test_account_activities {
         account.transaction(deposit_a).expect("Test setup: deposit transaction failed");
 
         let result = account.transaction(deposit_b);
-        assert!(result.is_err(), "Expected second deposit transaction to fail");
+        assert_eq!(result, Err(DuplicateTransaction(transaction_id)),
+                   "Expected second deposit transaction to fail");
 
         assert_eq!(account.available(), dec!(100.0));
         assert_eq!(account.held(), dec!(0.0));
@@ -315,7 +1289,7 @@ mod test_account_activities {
         let mut account = Account::default();
         account.transaction(deposit).expect("Test setup: deposit transaction failed");
 
-        let result = account.transaction(dispute);
+        let result = account.transaction(dispute.clone());
         assert!(result.is_ok(), "Expected dispute to succeed: {:?}: {:?}", dispute, result);
         assert_eq!(account.available(), dec!(0.0));
         assert_eq!(account.held(), dec!(100.0));
@@ -330,10 +1304,14 @@ mod test_account_activities {
         );
 
         let mut account_manager = Account::default();
-        let result = account_manager.transaction(dispute);
+        let result = account_manager.transaction(dispute.clone());
         assert!(result.is_ok(),
                 "Expected dispute of non-existing transaction to succeed: {:?}: {:?}",
                 dispute, result);
+        let effect = result.expect("Test setup: dispute failed");
+        assert_eq!(effect.status(), EffectStatus::UnknownTransaction,
+                   "Expected dispute of non-existing transaction to be reported as ignored");
+        assert_eq!(effect.before(), effect.after());
     }
 
     #[test]
@@ -363,11 +1341,11 @@ mod test_account_activities {
         account.transaction(deposit_a).expect("Test setup: deposit transaction failed");
         account.transaction(deposit_b).expect("Test setup: deposit transaction failed");
 
-        let result = account.transaction(dispute_a);
+        let result = account.transaction(dispute_a.clone());
         assert!(result.is_ok(),
                 "Expected dispute to succeed: {:?}: {:?}", dispute_a, result);
 
-        let result = account.transaction(dispute_b);
+        let result = account.transaction(dispute_b.clone());
         assert!(result.is_ok(),
                 "Expected dispute to succeed: {:?}: {:?}", dispute_b, result);
 
@@ -390,9 +1368,9 @@ mod test_account_activities {
 
         let mut account = Account::default();
         account.transaction(deposit).expect("Test setup: deposit transaction failed");
-        account.transaction(dispute).expect("Test setup: dispute failed");
+        account.transaction(dispute.clone()).expect("Test setup: dispute failed");
 
-        let result = account.transaction(dispute);
+        let result = account.transaction(dispute.clone());
         assert!(result.is_err(),
                 "Expected dispute on already disputed transaction to fail: {:?}", dispute);
     }
@@ -417,7 +1395,7 @@ mod test_account_activities {
         account.transaction(deposit).expect("Test setup: deposit transaction failed");
         account.transaction(dispute).expect("Test setup: dispute failed");
 
-        let result = account.transaction(resolve);
+        let result = account.transaction(resolve.clone());
         assert!(result.is_ok(), "Expected resolution to succeed: {:?}: {:?}", resolve, result);
         assert_eq!(account.available(), dec!(50.0));
         assert_eq!(account.held(), dec!(0.0));
@@ -428,7 +1406,7 @@ mod test_account_activities {
         let resolve = AccountActivity::resolve(TransactionID::default(), ClientID::default());
 
         let mut account = Account::default();
-        let result = account.transaction(resolve);
+        let result = account.transaction(resolve.clone());
         assert!(result.is_ok(),
                 "Expected resolution of non-existent dispute case to succeed: {:?}: {:?}",
                 resolve, result);
@@ -454,7 +1432,7 @@ mod test_account_activities {
         account.transaction(deposit).expect("Test setup: deposit transaction failed");
         account.transaction(dispute).expect("Test setup: dispute failed");
 
-        let result = account.transaction(chargeback);
+        let result = account.transaction(chargeback.clone());
         assert!(result.is_ok(),
                 "Expected chargeback to succeed: {:?}: {:?}", chargeback, result);
         assert_eq!(account.held(), dec!(0.0));
@@ -466,7 +1444,7 @@ mod test_account_activities {
         let chargeback = AccountActivity::chargeback(TransactionID::default(), ClientID::default());
 
         let mut account = Account::default();
-        let result = account.transaction(chargeback);
+        let result = account.transaction(chargeback.clone());
         assert!(result.is_ok(),
                 "Expected chargeback for non-existent dispute case to succeed: {:?}: {:?}",
                 chargeback, result);
@@ -491,10 +1469,673 @@ mod test_account_activities {
         let mut account = Account::default();
         account.transaction(deposit).expect("Test setup: deposit transaction failed");
         account.transaction(dispute).expect("Test setup: dispute failed");
-        account.transaction(chargeback).expect("Test setup: chargeback failed");
+        let effect = account.transaction(chargeback).expect("Test setup: chargeback failed");
 
         assert!(account.is_locked(),
                 "Expected account to be locked after successful chargeback");
+        assert_eq!(effect.lock_change(), Some(LockChange::Locked),
+                   "Expected chargeback to be reported as locking the account");
+        assert_eq!(effect.before().held, dec!(50.0));
+        assert_eq!(effect.after().total, dec!(0.0));
+    }
+
+    #[test]
+    fn locked_account_rejects_activities() {
+        let client_id = ClientID(7);
+        let deposit = AccountActivity::deposit(TransactionID::default(), client_id, dec!(50.0));
+
+        let mut account = Account::new(client_id);
+        account.lock = Some(AccountLock::chargeback(TransactionID::default()));
+
+        let result = account.transaction(deposit);
+        assert_eq!(result, Err(AccountLocked(client_id)),
+                   "Expected deposit on locked account to fail");
+        assert_eq!(account.available(), dec!(0.0));
+    }
+
+    /// A transaction record that cannot be written to.
+    #[derive(Debug)]
+    struct ReadOnlyStore;
+
+    impl TransactionStore for ReadOnlyStore {
+        fn get(&self, _: TransactionID) -> StorageResult<Option<TransactionRecord>> {
+            Ok(None)
+        }
+
+        fn insert(&mut self, _: TransactionID, _: TransactionRecord) -> StorageResult<bool> {
+            Err(io::Error::other("read-only store"))
+        }
+
+        fn update(&mut self, _: TransactionID, _: TransactionRecord) -> StorageResult<()> {
+            Err(io::Error::other("read-only store"))
+        }
+
+        fn evict(&mut self, _: TransactionID) -> StorageResult<()> {
+            Err(io::Error::other("read-only store"))
+        }
+
+        fn remove(&mut self, _: TransactionID) -> StorageResult<()> {
+            Err(io::Error::other("read-only store"))
+        }
+
+        fn record_count(&self) -> StorageResult<usize> {
+            Ok(0)
+        }
+
+        fn for_each_record(&self, _: &mut RecordVisitor<'_>) -> StorageResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn transfers_that_cannot_be_recorded_move_no_funds() {
+        let client_id = ClientID(1);
+        let mut account = Account::with_values(
+            client_id,
+            dec!(10.0),
+            dec!(0.0),
+            dec!(10.0),
+            LockStatus::Unlocked,
+        );
+        account.transaction_record = Box::new(ReadOnlyStore);
+
+        let result = account.transaction(
+            AccountActivity::transfer(TransactionID(1), client_id, ClientID(2), dec!(4.0)),
+        );
+        assert!(matches!(result, Err(Storage(_))), "Expected transfer to fail: {:?}", result);
+        assert_eq!(account.available(), dec!(10.0), "Expected no funds to be withdrawn");
+    }
+}
+
+#[cfg(test)]
+mod test_admin_actions {
+    use super::{Account, LockCause};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{AccountLocked, NotFrozen, NotLocked};
+    use crate::admin::AdminAction;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    const CLIENT: ClientID = ClientID(1);
+
+    #[test]
+    fn frozen_account_records_reason_and_operator() {
+        let mut account = Account::new(CLIENT);
+        let freeze = AdminAction::new(TransactionID(1), CLIENT, "suspected fraud")
+            .with_operator("alice");
+        account
+            .transaction(AccountActivity::Freeze(freeze))
+            .expect("Expected freeze to succeed");
+
+        let lock = account.lock().expect("Expected account to be locked");
+        assert_eq!(lock.cause(), LockCause::Freeze);
+        assert_eq!(lock.reason(), "suspected fraud");
+        assert_eq!(lock.operator(), Some("alice"));
+        assert_eq!(account.balances().lock_reason(), Some("suspected fraud"));
+
+        let result = account.transaction(
+            AccountActivity::deposit(TransactionID(2), CLIENT, dec!(1.0)),
+        );
+        assert_eq!(result, Err(AccountLocked(CLIENT)), "Expected frozen account to reject deposit");
+    }
+
+    #[test]
+    fn unfrozen_account_accepts_activities() {
+        let mut account = Account::new(CLIENT);
+        account
+            .transaction(AccountActivity::freeze(TransactionID(1), CLIENT, "review"))
+            .expect("Test setup: freeze failed");
+        account
+            .transaction(AccountActivity::unfreeze(TransactionID(2), CLIENT, "review passed"))
+            .expect("Expected unfreeze to succeed");
+
+        assert!(!account.is_locked(), "Expected account to be unlocked");
+        assert_eq!(account.balances().lock_reason(), None);
+        account
+            .transaction(AccountActivity::deposit(TransactionID(3), CLIENT, dec!(1.0)))
+            .expect("Expected deposit to succeed");
+    }
+
+    #[test]
+    fn chargeback_lock_is_only_lifted_by_unlock() {
+        let mut account = Account::new(CLIENT).with_activities([
+            AccountActivity::deposit(TransactionID(1), CLIENT, dec!(10.0)),
+            AccountActivity::deposit(TransactionID(2), CLIENT, dec!(5.0)),
+            AccountActivity::dispute(TransactionID(2), CLIENT),
+            AccountActivity::chargeback(TransactionID(2), CLIENT),
+        ]);
+        let lock = account.lock().expect("Expected account to be locked");
+        assert_eq!(lock.cause(), LockCause::Chargeback);
+        assert_eq!(lock.reason(), "chargeback of transaction 2");
+
+        let result = account.transaction(AccountActivity::unfreeze(TransactionID(3), CLIENT, ""));
+        assert_eq!(result, Err(NotFrozen(CLIENT)), "Expected unfreeze to fail");
+
+        account
+            .transaction(AccountActivity::unlock(TransactionID(4), CLIENT, "refund settled"))
+            .expect("Expected unlock to succeed");
+        assert!(!account.is_locked(), "Expected account to be unlocked");
+        assert_eq!(account.total(), dec!(10.0));
+    }
+
+    #[test]
+    fn unlocking_unlocked_account_fails() {
+        let mut account = Account::new(CLIENT);
+        let result = account.transaction(AccountActivity::unlock(TransactionID(1), CLIENT, ""));
+        assert_eq!(result, Err(NotLocked(CLIENT)), "Expected unlock to fail");
+    }
+}
+
+#[cfg(test)]
+mod test_dispute_window {
+    use super::{Account, AccountOptions, TransactionState};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{
+        DisputeWindowExpired, DuplicateTransaction, NotDisputed,
+    };
+    use crate::timestamp::Timestamp;
+    use crate::transaction::TransactionID;
+    use crate::transfer::Transfer;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+
+    const CLIENT: ClientID = ClientID(1);
+    const WINDOW: Duration = Duration::from_secs(100);
+
+    fn account(evict_expired: bool) -> Account {
+        let options = AccountOptions::default()
+            .with_dispute_window(WINDOW)
+            .with_expired_eviction(evict_expired);
+        Account::with_options(CLIENT, options).with_activities([
+            AccountActivity::deposit(TransactionID(1), CLIENT, dec!(10.0))
+                .with_timestamp(Timestamp(1_000)),
+            AccountActivity::deposit(TransactionID(2), CLIENT, dec!(10.0))
+                .with_timestamp(Timestamp(1_050)),
+        ])
+    }
+
+    fn dispute(tx: u32, timestamp: u64) -> AccountActivity {
+        AccountActivity::dispute(TransactionID(tx), CLIENT).with_timestamp(Timestamp(timestamp))
+    }
+
+    #[test]
+    fn disputes_within_the_window_are_accepted() {
+        let mut account = account(false);
+        account.transaction(dispute(1, 1_100)).expect("Expected dispute to be accepted");
+        assert_eq!(account.held(), dec!(10.0));
+    }
+
+    #[test]
+    fn late_disputes_are_rejected() {
+        let mut account = account(false);
+        let result = account.transaction(dispute(1, 1_101));
+        assert_eq!(result, Err(DisputeWindowExpired(TransactionID(1))));
+        assert_eq!(account.held(), dec!(0.0), "Expected no funds to be held");
+
+        // Disputes without a timestamp take place at the latest point in time of the account
+        let result = account.transaction(AccountActivity::dispute(TransactionID(2), CLIENT));
+        assert!(result.is_ok(), "Expected dispute at the latest point in time to be accepted");
+    }
+
+    #[test]
+    fn expired_transactions_are_evicted() {
+        let mut account = account(true);
+        account.transaction(dispute(2, 1_060)).expect("Test setup: dispute failed");
+        let deposit = AccountActivity::deposit(TransactionID(3), CLIENT, dec!(1.0));
+        account
+            .transaction(deposit.with_timestamp(Timestamp(1_200)))
+            .expect("Test setup: deposit failed");
+
+        assert_eq!(account.transaction_state(TransactionID(1)).ok(),
+                   Some(Some(TransactionState::Evicted)),
+                   "Expected expired transaction to be evicted");
+        assert_eq!(account.transaction_state(TransactionID(2)).ok(),
+                   Some(Some(TransactionState::Disputed)),
+                   "Expected disputed transaction to be kept");
+        let mut transaction_ids = Vec::new();
+        account
+            .for_each_transaction_id(|transaction_id| {
+                transaction_ids.push(transaction_id);
+                Ok(())
+            })
+            .expect("Expected transaction IDs to be read");
+        assert_eq!(transaction_ids, vec![TransactionID(1), TransactionID(2), TransactionID(3)]);
+
+        let result = account.transaction(dispute(1, 1_200));
+        assert_eq!(result, Err(DisputeWindowExpired(TransactionID(1))));
+        let result = account.transaction(AccountActivity::resolve(TransactionID(1), CLIENT));
+        assert_eq!(result, Err(NotDisputed(TransactionID(1))));
+        let deposit = AccountActivity::deposit(TransactionID(1), CLIENT, dec!(1.0));
+        assert_eq!(account.transaction(deposit), Err(DuplicateTransaction(TransactionID(1))),
+                   "Expected evicted transaction ID to be rejected");
+    }
+
+    #[test]
+    fn evicted_records_are_removed_from_the_store() {
+        let mut account = account(true);
+        assert_eq!(account.transaction_record.record_count().ok(), Some(2));
+
+        let deposit = AccountActivity::deposit(TransactionID(3), CLIENT, dec!(1.0));
+        account
+            .transaction(deposit.with_timestamp(Timestamp(1_151)))
+            .expect("Test setup: deposit failed");
+        assert_eq!(account.transaction_record.record_count().ok(), Some(1),
+                   "Expected records of both expired transactions to be removed");
+    }
+
+    #[test]
+    fn eviction_is_delayed_for_late_disputes() {
+        let options = account(true).options.with_eviction_delay(Duration::from_secs(50));
+        let mut account = Account::with_options(CLIENT, options).with_activities([
+            AccountActivity::deposit(TransactionID(1), CLIENT, dec!(10.0))
+                .with_timestamp(Timestamp(1_000)),
+            AccountActivity::deposit(TransactionID(2), CLIENT, dec!(10.0))
+                .with_timestamp(Timestamp(1_150)),
+        ]);
+
+        account
+            .transaction(dispute(1, 1_100))
+            .expect("Expected late dispute within the window to be accepted");
+        assert_eq!(account.held(), dec!(10.0));
+
+        let deposit = AccountActivity::deposit(TransactionID(3), CLIENT, dec!(1.0));
+        account
+            .transaction(deposit.with_timestamp(Timestamp(1_151)))
+            .expect("Test setup: deposit failed");
+        assert_eq!(account.transaction_state(TransactionID(1)).ok(),
+                   Some(Some(TransactionState::Disputed)),
+                   "Expected disputed transaction to be kept");
+    }
+
+    #[test]
+    fn transfers_expire_in_both_accounts() {
+        let mut source = account(false);
+        let mut destination = Account::with_options(ClientID(2), source.options.clone());
+        let transfer = Transfer::new(TransactionID(3), CLIENT, ClientID(2), dec!(5.0))
+            .with_timestamp(Timestamp(1_050));
+        source
+            .transaction(AccountActivity::Transfer(transfer))
+            .expect("Test setup: outgoing leg failed");
+        destination.receive_transfer(transfer).expect("Test setup: incoming leg failed");
+
+        let result = source.transaction(dispute(3, 1_151));
+        assert_eq!(result, Err(DisputeWindowExpired(TransactionID(3))),
+                   "Expected outgoing leg to expire");
+        let dispute = AccountActivity::dispute(TransactionID(3), ClientID(2))
+            .with_timestamp(Timestamp(1_151));
+        assert_eq!(destination.transaction(dispute), Err(DisputeWindowExpired(TransactionID(3))),
+                   "Expected incoming leg to expire");
+    }
+
+    #[test]
+    fn concluded_transactions_are_evicted_once_expired() {
+        let mut account = account(true);
+        account.transaction(dispute(2, 1_060)).expect("Test setup: dispute failed");
+        let deposit = AccountActivity::deposit(TransactionID(3), CLIENT, dec!(1.0));
+        account
+            .transaction(deposit.with_timestamp(Timestamp(1_200)))
+            .expect("Test setup: deposit failed");
+
+        let resolve = AccountActivity::resolve(TransactionID(2), CLIENT);
+        account
+            .transaction(resolve.with_timestamp(Timestamp(1_201)))
+            .expect("Expected resolve of disputed transaction to succeed");
+        let deposit = AccountActivity::deposit(TransactionID(4), CLIENT, dec!(1.0));
+        account
+            .transaction(deposit.with_timestamp(Timestamp(1_202)))
+            .expect("Test setup: deposit failed");
+        assert_eq!(account.transaction_state(TransactionID(2)).ok(),
+                   Some(Some(TransactionState::Evicted)),
+                   "Expected resolved transaction to be evicted");
+    }
+}
+
+#[cfg(test)]
+mod test_locked_account_policy {
+    use super::{Account, AccountOptions, LockCause, LockedAccountPolicy};
+    use crate::account_activity::AccountActivityError::{AccountLocked, NotFrozen};
+    use crate::account_activity::{AccountActivity, ActivityKind};
+    use crate::transaction::TransactionID;
+    use crate::transfer::Transfer;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    const CLIENT: ClientID = ClientID(1);
+
+    /// Returns a frozen account with funds held by a dispute that was opened before the freeze.
+    fn frozen_account(policy: LockedAccountPolicy) -> Account {
+        let options = AccountOptions::default().with_locked_accounts(policy);
+        Account::with_options(CLIENT, options).with_activities([
+            AccountActivity::deposit(TransactionID(1), CLIENT, dec!(10.0)),
+            AccountActivity::deposit(TransactionID(2), CLIENT, dec!(5.0)),
+            AccountActivity::dispute(TransactionID(2), CLIENT),
+            AccountActivity::freeze(TransactionID(3), CLIENT, "investigation"),
+        ])
+    }
+
+    #[test]
+    fn locked_accounts_reject_everything_by_default() {
+        let policy = LockedAccountPolicy::default();
+        assert!(!policy.accepts(ActivityKind::Deposit), "Expected deposits to be rejected");
+        assert!(policy.accepts(ActivityKind::Unlock), "Expected unlocks to be accepted");
+
+        let mut account = frozen_account(policy);
+        let result = account.transaction(AccountActivity::resolve(TransactionID(2), CLIENT));
+        assert_eq!(result, Err(AccountLocked(CLIENT)), "Expected resolve to be rejected");
+        assert_eq!(account.held(), dec!(5.0));
+    }
+
+    #[test]
+    fn locked_accounts_accept_activities_allowed_by_policy() {
+        let policy = [ActivityKind::Deposit, ActivityKind::Resolve, ActivityKind::Chargeback]
+            .into_iter()
+            .collect::<LockedAccountPolicy>();
+        let mut account = frozen_account(policy);
+
+        account
+            .transaction(AccountActivity::resolve(TransactionID(2), CLIENT))
+            .expect("Expected resolve to be accepted");
+        account
+            .transaction(AccountActivity::deposit(TransactionID(4), CLIENT, dec!(1.0)))
+            .expect("Expected deposit to be accepted");
+        let transfer = Transfer::new(TransactionID(5), ClientID(2), CLIENT, dec!(1.0));
+        account.receive_transfer(transfer).expect("Expected incoming transfer to be accepted");
+
+        let result = account.transaction(
+            AccountActivity::withdrawal(TransactionID(6), CLIENT, dec!(1.0)),
+        );
+        assert_eq!(result, Err(AccountLocked(CLIENT)), "Expected withdrawal to be rejected");
+        assert!(account.is_locked(), "Expected account to stay locked");
+        assert_eq!((account.available(), account.held()), (dec!(17.0), dec!(0.0)));
+    }
+
+    #[test]
+    fn freezes_never_replace_chargeback_locks() {
+        let policy = LockedAccountPolicy::default().accept(ActivityKind::Freeze);
+        let mut account = frozen_account(policy);
+        account
+            .transaction(AccountActivity::freeze(TransactionID(4), CLIENT, "updated reason"))
+            .expect("Expected frozen account to be frozen again");
+        assert_eq!(account.lock().map(|lock| lock.reason()), Some("updated reason"));
+
+        let options = AccountOptions::default().with_locked_accounts(policy);
+        let mut account = Account::with_options(CLIENT, options).with_activities([
+            AccountActivity::deposit(TransactionID(1), CLIENT, dec!(10.0)),
+            AccountActivity::dispute(TransactionID(1), CLIENT),
+            AccountActivity::chargeback(TransactionID(1), CLIENT),
+        ]);
+        let result = account.transaction(AccountActivity::freeze(TransactionID(2), CLIENT, ""));
+        assert_eq!(result, Err(AccountLocked(CLIENT)), "Expected chargeback lock to be kept");
+        let result = account.transaction(AccountActivity::unfreeze(TransactionID(3), CLIENT, ""));
+        assert_eq!(result, Err(NotFrozen(CLIENT)), "Expected unfreeze to fail");
+        assert_eq!(account.lock().map(|lock| lock.cause()), Some(LockCause::Chargeback));
+    }
+}
+
+#[cfg(test)]
+mod test_dispute_lifecycle {
+    use super::{Account, TransactionState};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{DisputeConcluded, NotDisputed};
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    fn account_with_deposit() -> Account {
+        let deposit = AccountActivity::deposit(
+            TransactionID::default(),
+            ClientID::default(),
+            dec!(50.0),
+        );
+        let mut account = Account::default();
+        account.transaction(deposit).expect("Test setup: deposit transaction failed");
+        account
+    }
+
+    fn dispute() -> AccountActivity {
+        AccountActivity::dispute(TransactionID::default(), ClientID::default())
+    }
+
+    fn resolve() -> AccountActivity {
+        AccountActivity::resolve(TransactionID::default(), ClientID::default())
+    }
+
+    fn chargeback() -> AccountActivity {
+        AccountActivity::chargeback(TransactionID::default(), ClientID::default())
+    }
+
+    #[test]
+    fn only_permitted_transitions_are_accepted() {
+        let cases = [
+            (TransactionState::Processed, dispute(), Some(TransactionState::Disputed)),
+            (TransactionState::Processed, resolve(), None),
+            (TransactionState::Processed, chargeback(), None),
+            (TransactionState::Disputed, dispute(), None),
+            (TransactionState::Disputed, resolve(), Some(TransactionState::Resolved)),
+            (TransactionState::Disputed, chargeback(), Some(TransactionState::ChargedBack)),
+            (TransactionState::Resolved, dispute(), None),
+            (TransactionState::Resolved, resolve(), None),
+            (TransactionState::Resolved, chargeback(), None),
+            (TransactionState::ChargedBack, dispute(), None),
+            (TransactionState::ChargedBack, resolve(), None),
+            (TransactionState::ChargedBack, chargeback(), None),
+        ];
+        for (state, activity, expected) in cases {
+            assert_eq!(state.next(&activity), expected,
+                       "Unexpected transition from '{}' on {}", state, activity);
+        }
+    }
+
+    #[test]
+    fn deposit_is_recorded_as_processed() {
+        let account = account_with_deposit();
+        assert_eq!(account.transaction_state(TransactionID::default()).ok(),
+                   Some(Some(TransactionState::Processed)));
+    }
+
+    #[test]
+    fn resolve_of_undisputed_transaction_fails() {
+        let mut account = account_with_deposit();
+
+        let result = account.transaction(resolve());
+        assert_eq!(result, Err(NotDisputed(TransactionID::default())),
+                   "Expected resolution of undisputed transaction to fail");
+        assert_eq!(account.available(), dec!(50.0));
+        assert_eq!(account.held(), dec!(0.0));
+        assert_eq!(account.total(), dec!(50.0));
+    }
+
+    #[test]
+    fn chargeback_of_undisputed_transaction_fails() {
+        let mut account = account_with_deposit();
+
+        let result = account.transaction(chargeback());
+        assert_eq!(result, Err(NotDisputed(TransactionID::default())),
+                   "Expected chargeback of undisputed transaction to fail");
+        assert_eq!(account.available(), dec!(50.0));
+        assert_eq!(account.held(), dec!(0.0));
+        assert_eq!(account.total(), dec!(50.0));
+        assert!(!account.is_locked(), "Expected account to remain unlocked");
+    }
+
+    #[test]
+    fn resolving_same_dispute_twice_fails() {
+        let mut account = account_with_deposit();
+        account.transaction(dispute()).expect("Test setup: dispute failed");
+        account.transaction(resolve()).expect("Test setup: resolve failed");
+
+        let result = account.transaction(resolve());
+        assert_eq!(result, Err(DisputeConcluded {
+            transaction_id: TransactionID::default(),
+            state: TransactionState::Resolved,
+        }), "Expected second resolution to fail");
+        assert_eq!(account.available(), dec!(50.0));
+        assert_eq!(account.held(), dec!(0.0));
+        assert_eq!(account.total(), dec!(50.0));
+    }
+
+    #[test]
+    fn chargeback_after_resolve_fails() {
+        let mut account = account_with_deposit();
+        account.transaction(dispute()).expect("Test setup: dispute failed");
+        account.transaction(resolve()).expect("Test setup: resolve failed");
+
+        let result = account.transaction(chargeback());
+        assert_eq!(result, Err(DisputeConcluded {
+            transaction_id: TransactionID::default(),
+            state: TransactionState::Resolved,
+        }), "Expected chargeback of resolved dispute to fail");
+        assert_eq!(account.available(), dec!(50.0));
+        assert_eq!(account.held(), dec!(0.0));
+        assert_eq!(account.total(), dec!(50.0));
+        assert!(!account.is_locked(), "Expected account to remain unlocked");
+    }
+
+    #[test]
+    fn dispute_after_resolve_fails() {
+        let mut account = account_with_deposit();
+        account.transaction(dispute()).expect("Test setup: dispute failed");
+        account.transaction(resolve()).expect("Test setup: resolve failed");
+
+        let result = account.transaction(dispute());
+        assert_eq!(result, Err(DisputeConcluded {
+            transaction_id: TransactionID::default(),
+            state: TransactionState::Resolved,
+        }), "Expected dispute of resolved transaction to fail");
+        assert_eq!(account.available(), dec!(50.0));
+        assert_eq!(account.held(), dec!(0.0));
+    }
+
+    #[test]
+    fn dispute_lifecycle_is_tracked() {
+        let mut account = account_with_deposit();
+
+        account.transaction(dispute()).expect("Test setup: dispute failed");
+        assert_eq!(account.transaction_state(TransactionID::default()).ok(),
+                   Some(Some(TransactionState::Disputed)));
+
+        account.transaction(chargeback()).expect("Test setup: chargeback failed");
+        assert_eq!(account.transaction_state(TransactionID::default()).ok(),
+                   Some(Some(TransactionState::ChargedBack)));
+    }
+}
+
+#[cfg(test)]
+mod test_withdrawal_disputes {
+    use super::{Account, AccountOptions, TransactionState, WithdrawalDisputes};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{
+        TransactionFailed, WithdrawalNotDisputable,
+    };
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    const WITHDRAWAL: TransactionID = TransactionID(2);
+
+    fn account_with_withdrawal(policy: WithdrawalDisputes) -> Account {
+        let client_id = ClientID::default();
+        let options = AccountOptions::default().with_withdrawal_disputes(policy);
+        Account::with_options(client_id, options).with_activities([
+            AccountActivity::deposit(TransactionID(1), client_id, dec!(100.0)),
+            AccountActivity::withdrawal(WITHDRAWAL, client_id, dec!(40.0)),
+        ])
+    }
+
+    fn balances(account: &Account) -> (String, String, String) {
+        (
+            account.available().to_string(),
+            account.held().to_string(),
+            account.total().to_string(),
+        )
+    }
+
+    #[test]
+    fn disputed_withdrawal_is_provisionally_credited() {
+        let mut account = account_with_withdrawal(WithdrawalDisputes::ProvisionalCredit);
+        account
+            .transaction(AccountActivity::dispute(WITHDRAWAL, ClientID::default()))
+            .expect("Expected dispute of withdrawal to succeed");
+
+        assert_eq!(balances(&account), ("60.0".into(), "40.0".into(), "100.0".into()),
+                   "Expected withdrawn amount to be held");
+    }
+
+    #[test]
+    fn resolved_withdrawal_dispute_removes_provisional_credit() {
+        let mut account = account_with_withdrawal(WithdrawalDisputes::ProvisionalCredit);
+        for activity in [
+            AccountActivity::dispute(WITHDRAWAL, ClientID::default()),
+            AccountActivity::resolve(WITHDRAWAL, ClientID::default()),
+        ] {
+            account.transaction(activity).expect("Expected dispute case step to succeed");
+        }
+
+        assert_eq!(balances(&account), ("60.0".into(), "0.0".into(), "60.0".into()),
+                   "Expected withdrawal to stand");
+        assert!(!account.is_locked(), "Expected account to remain unlocked");
+    }
+
+    #[test]
+    fn charged_back_withdrawal_is_reversed() {
+        let mut account = account_with_withdrawal(WithdrawalDisputes::ProvisionalCredit);
+        for activity in [
+            AccountActivity::dispute(WITHDRAWAL, ClientID::default()),
+            AccountActivity::chargeback(WITHDRAWAL, ClientID::default()),
+        ] {
+            account.transaction(activity).expect("Expected dispute case step to succeed");
+        }
+
+        assert_eq!(balances(&account), ("100.0".into(), "0.0".into(), "100.0".into()),
+                   "Expected withdrawn amount to be returned");
+        assert!(account.is_locked(), "Expected account to be locked after chargeback");
+    }
+
+    #[test]
+    fn disputed_deposit_holds_funds() {
+        let mut account = account_with_withdrawal(WithdrawalDisputes::ProvisionalCredit);
+        account
+            .transaction(AccountActivity::dispute(TransactionID(1), ClientID::default()))
+            .expect("Expected dispute of deposit to succeed");
+
+        assert_eq!(balances(&account), ("-40.0".into(), "100.0".into(), "60.0".into()),
+                   "Expected deposited amount to be held");
+    }
+
+    #[test]
+    fn withdrawal_disputes_can_be_rejected() {
+        let mut account = account_with_withdrawal(WithdrawalDisputes::Reject);
+        let result = account.transaction(AccountActivity::dispute(WITHDRAWAL, ClientID::default()));
+
+        assert_eq!(result, Err(WithdrawalNotDisputable(WITHDRAWAL)));
+        assert_eq!(balances(&account), ("60.0".into(), "0.0".into(), "60.0".into()));
+        assert_eq!(account.transaction_state(WITHDRAWAL).ok(),
+                   Some(Some(TransactionState::Processed)));
+    }
+
+    #[test]
+    fn failed_withdrawals_cannot_be_disputed() {
+        let failed = TransactionID(3);
+        for policy in [WithdrawalDisputes::ProvisionalCredit, WithdrawalDisputes::Reject] {
+            let mut account = account_with_withdrawal(policy);
+            let result = account
+                .transaction(AccountActivity::withdrawal(failed, ClientID::default(), dec!(1000)));
+            assert!(result.is_err(), "Expected withdrawal exceeding available funds to fail");
+            assert_eq!(account.transaction_state(failed).ok(),
+                       Some(Some(TransactionState::Failed)));
+
+            for activity in [
+                AccountActivity::dispute(failed, ClientID::default()),
+                AccountActivity::chargeback(failed, ClientID::default()),
+            ] {
+                assert_eq!(account.transaction(activity), Err(TransactionFailed(failed)),
+                           "Expected dispute case of failed withdrawal to fail");
+            }
+            assert_eq!(balances(&account), ("60.0".into(), "0.0".into(), "60.0".into()),
+                       "Expected balances to be unchanged with {:?}", policy);
+        }
     }
 }
 
@@ -523,8 +2164,8 @@ mod test_accounting {
 
         for invalid_value in invalid_values {
             let result = account.deposit(invalid_value);
-            assert!(result.is_err(),
-                    "Expected deposit with invalid value to fail: {:?}", invalid_value);
+            assert_eq!(result, Err(NegativeAmount(invalid_value)),
+                       "Expected deposit with invalid value to fail: {:?}", invalid_value);
             assert_eq!(account.available(), dec!(0.0));
             assert_eq!(account.total(), dec!(0.0));
         }
@@ -566,7 +2207,10 @@ mod test_accounting {
         account.deposit(available_funds).expect("Test setup: deposit failed");
 
         let result = account.withdraw(available_funds + dec!(0.1));
-        assert!(result.is_err(), "Expected withdrawal exceeding available funds to fail");
+        assert_eq!(result, Err(InsufficientFunds {
+            requested: available_funds + dec!(0.1),
+            available: available_funds,
+        }), "Expected withdrawal exceeding available funds to fail");
         assert_eq!(account.available(), available_funds);
         assert_eq!(account.total(), available_funds);
     }
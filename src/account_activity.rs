@@ -1,35 +1,186 @@
+use crate::account::TransactionState;
+use crate::admin::AdminAction;
 use crate::dispute::DisputeCase;
+use crate::storage::StorageFailure;
+use crate::timestamp::Timestamp;
 use crate::transaction::{Transaction, TransactionID};
+use crate::transfer::Transfer;
 use crate::ClientID;
 use rust_decimal::Decimal;
 use std::fmt::{Display, Formatter};
+use std::io;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+/// Reasons for an [`AccountActivity`] to be rejected by an [`Account`].
+///
+/// [`Account`]: crate::account::Account
+#[derive(Error, Debug, PartialEq, Clone)]
 pub enum AccountActivityError {
-    /// Indicates that the payload of a transaction is invalid.
-    #[error("invalid transaction: {0}")]
-    InvalidTransaction(String),
+    /// Indicates that the amount of a transaction is negative.
+    #[error("invalid transaction: amount {0} must not be negative")]
+    NegativeAmount(Decimal),
 
-    /// Indicates that a transaction could not be executed.
-    ///
-    /// This covers cases such as an attempt of a withdrawal with insufficient funds or an internal
-    /// processing error.
-    #[error("failed transaction: {0}")]
-    FailedTransaction(String),
+    /// Indicates that a withdrawal exceeds the available funds of the account.
+    #[error("failed transaction: insufficient funds (requested {requested}, available {available})")]
+    InsufficientFunds {
+        requested: Decimal,
+        available: Decimal,
+    },
+
+    /// Indicates that a transaction with the same ID has already been recorded.
+    #[error("failed transaction: transaction {0} already recorded")]
+    DuplicateTransaction(TransactionID),
+
+    /// Indicates that the account is locked and does not accept any further activities.
+    #[error("failed transaction: account {0} is locked")]
+    AccountLocked(ClientID),
+
+    /// Indicates that a dispute case references a transaction that has never been recorded.
+    #[error("failed dispute case: unknown transaction {0}")]
+    UnknownTransaction(TransactionID),
+
+    /// Indicates that a dispute case references a transaction of another client.
+    #[error("failed dispute case: transaction {transaction_id} is not owned by client {client_id}")]
+    ForeignTransaction {
+        transaction_id: TransactionID,
+        client_id: ClientID,
+    },
+
+    /// Indicates that a dispute was initiated on a transaction that is already disputed.
+    #[error("failed dispute case: transaction {0} is already disputed")]
+    AlreadyDisputed(TransactionID),
+
+    /// Indicates that a dispute case was resolved or charged back without being initiated.
+    #[error("failed dispute case: transaction {0} is not disputed")]
+    NotDisputed(TransactionID),
+
+    /// Indicates that a dispute case references a transaction that has failed and thus has not
+    /// moved any funds.
+    #[error("failed dispute case: transaction {0} has failed")]
+    TransactionFailed(TransactionID),
 
-    /// Indicates that a dispute case could not be executed.
+    /// Indicates that a dispute case step references a transaction whose dispute case has already
+    /// been concluded.
     ///
-    /// This covers cases such as a dispute being initiated on an already disputed transaction.
-    #[error("failed dispute case: {0}")]
-    FailedDisputeCase(String),
+    /// See [`TransactionState`] for the permitted steps of a dispute case.
+    #[error("failed dispute case: dispute of transaction {transaction_id} already {state}")]
+    DisputeConcluded {
+        transaction_id: TransactionID,
+        state: TransactionState,
+    },
+
+    /// Indicates that a dispute was initiated on a withdrawal while the
+    /// [`WithdrawalDisputes`](crate::account::WithdrawalDisputes) policy rejects such disputes.
+    #[error("failed dispute case: withdrawal {0} cannot be disputed")]
+    WithdrawalNotDisputable(TransactionID),
+
+    /// Indicates that a dispute was initiated after the
+    /// [dispute window](crate::account::AccountOptions::with_dispute_window) of the transaction
+    /// has expired.
+    #[error("failed dispute case: dispute window of transaction {0} has expired")]
+    DisputeWindowExpired(TransactionID),
+
+    /// Indicates that an activity precedes an earlier record of the input by more than the
+    /// tolerance of the [`OutOfOrderPolicy`](crate::chronology::OutOfOrderPolicy).
+    #[error("out of order: activity at {timestamp} precedes earlier activity at {latest}")]
+    OutOfOrder {
+        timestamp: Timestamp,
+        latest: Timestamp,
+    },
+
+    /// Indicates that a transfer names the same client as source and destination.
+    #[error("invalid transfer: transfer {0} has the same source and destination account")]
+    SelfTransfer(TransactionID),
+
+    /// Indicates that an account was unfrozen or unlocked although it is not locked.
+    #[error("failed admin action: account {0} is not locked")]
+    NotLocked(ClientID),
+
+    /// Indicates that an account was unfrozen although it was locked for another reason than a
+    /// freeze, e.g. a chargeback. Such accounts must be [unlocked](AccountActivity::Unlock).
+    #[error("failed admin action: account {0} is not frozen")]
+    NotFrozen(ClientID),
+
+    /// Indicates that the transaction record of the account could not be accessed. Unlike all
+    /// other errors, this error is not caused by the activity and aborts processing.
+    #[error("failed to access transaction record")]
+    Storage(#[source] StorageFailure),
+}
+
+impl From<io::Error> for AccountActivityError {
+    fn from(err: io::Error) -> Self {
+        AccountActivityError::Storage(err.into())
+    }
+}
+
+impl AccountActivityError {
+    /// Returns a short, stable identifier of the kind of error, suitable for reports.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AccountActivityError::NegativeAmount(_) => "negative_amount",
+            AccountActivityError::InsufficientFunds { .. } => "insufficient_funds",
+            AccountActivityError::DuplicateTransaction(_) => "duplicate_transaction",
+            AccountActivityError::AccountLocked(_) => "account_locked",
+            AccountActivityError::UnknownTransaction(_) => "unknown_transaction",
+            AccountActivityError::ForeignTransaction { .. } => "foreign_transaction",
+            AccountActivityError::AlreadyDisputed(_) => "already_disputed",
+            AccountActivityError::NotDisputed(_) => "not_disputed",
+            AccountActivityError::TransactionFailed(_) => "transaction_failed",
+            AccountActivityError::DisputeConcluded { .. } => "dispute_concluded",
+            AccountActivityError::WithdrawalNotDisputable(_) => "withdrawal_not_disputable",
+            AccountActivityError::DisputeWindowExpired(_) => "dispute_window_expired",
+            AccountActivityError::OutOfOrder { .. } => "out_of_order",
+            AccountActivityError::SelfTransfer(_) => "self_transfer",
+            AccountActivityError::NotLocked(_) => "not_locked",
+            AccountActivityError::NotFrozen(_) => "not_frozen",
+            AccountActivityError::Storage(_) => "storage_failure",
+        }
+    }
 }
 
 pub type AccountActivityResult<T> = Result<T, AccountActivityError>;
 
+/// The kind of an [`AccountActivity`], as it appears in the `type` column of the input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ActivityKind {
+    Deposit,
+    Withdrawal,
+    Transfer,
+    Dispute,
+    Resolve,
+    Chargeback,
+    Freeze,
+    Unfreeze,
+    Unlock,
+}
+
+impl ActivityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivityKind::Deposit => "deposit",
+            ActivityKind::Withdrawal => "withdrawal",
+            ActivityKind::Transfer => "transfer",
+            ActivityKind::Dispute => "dispute",
+            ActivityKind::Resolve => "resolve",
+            ActivityKind::Chargeback => "chargeback",
+            ActivityKind::Freeze => "freeze",
+            ActivityKind::Unfreeze => "unfreeze",
+            ActivityKind::Unlock => "unlock",
+        }
+    }
+}
+
+impl Display for ActivityKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// Account activities are events that influence an [`Account`]s balance. These events could either 
-/// be [`Transaction`]s or [`DisputeCase`]s.
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// be [`Transaction`]s, [`Transfer`]s, [`DisputeCase`]s or [`AdminAction`]s.
+///
+/// [`Account`]: crate::account::Account
+#[derive(Debug, PartialEq, Clone)]
 pub enum AccountActivity {
     /// A [`Transaction`] where funds are added to an account, increasing the available and total
     /// balance of the account.
@@ -45,6 +196,13 @@ pub enum AccountActivity {
     /// accounts, or using checks or debit cards for purchases.
     Withdrawal(Transaction),
 
+    /// A [`Transfer`] of funds from the account of the client to the account of another client.
+    ///
+    /// Applied to a single account, a transfer withdraws the funds from the source account. The
+    /// deposit into the destination account is applied alongside it during
+    /// [processing](crate::processor::process_activities).
+    Transfer(Transfer),
+
     /// A dispute is the initiation of a [`DisputeCase`].
     ///
     /// It is a formal objection raised by a customer regarding a particular [`Transaction`].
@@ -78,6 +236,17 @@ pub enum AccountActivity {
     ///
     /// [`Dispute`]: AccountActivity::Dispute
     Chargeback(DisputeCase),
+
+    /// An [`AdminAction`] that locks the account until it is [unfrozen](AccountActivity::Unfreeze)
+    /// or [unlocked](AccountActivity::Unlock).
+    Freeze(AdminAction),
+
+    /// An [`AdminAction`] that lifts a [freeze](AccountActivity::Freeze) of the account.
+    Unfreeze(AdminAction),
+
+    /// An [`AdminAction`] that lifts any lock of the account, including locks caused by a
+    /// [`Chargeback`](AccountActivity::Chargeback).
+    Unlock(AdminAction),
 }
 
 impl AccountActivity {
@@ -89,6 +258,15 @@ impl AccountActivity {
         Self::Withdrawal(Transaction::new(transaction_id, client_id, amount))
     }
 
+    pub fn transfer(
+        transaction_id: TransactionID,
+        client_id: ClientID,
+        to_client_id: ClientID,
+        amount: Decimal,
+    ) -> Self {
+        Self::Transfer(Transfer::new(transaction_id, client_id, to_client_id, amount))
+    }
+
     pub fn dispute(transaction_id: TransactionID, client_id: ClientID) -> Self {
         Self::Dispute(DisputeCase::new(transaction_id, client_id))
     }
@@ -101,36 +279,138 @@ impl AccountActivity {
         Self::Chargeback(DisputeCase::new(transaction_id, client_id))
     }
 
+    pub fn freeze(
+        transaction_id: TransactionID,
+        client_id: ClientID,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self::Freeze(AdminAction::new(transaction_id, client_id, reason))
+    }
+
+    pub fn unfreeze(
+        transaction_id: TransactionID,
+        client_id: ClientID,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self::Unfreeze(AdminAction::new(transaction_id, client_id, reason))
+    }
+
+    pub fn unlock(
+        transaction_id: TransactionID,
+        client_id: ClientID,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self::Unlock(AdminAction::new(transaction_id, client_id, reason))
+    }
+
     pub fn transaction_id(&self) -> TransactionID {
         match self {
             AccountActivity::Deposit(transaction) => transaction.id(),
             AccountActivity::Withdrawal(transaction) => transaction.id(),
+            AccountActivity::Transfer(transfer) => transfer.id(),
             AccountActivity::Dispute(transaction) => transaction.id(),
             AccountActivity::Resolve(transaction) => transaction.id(),
             AccountActivity::Chargeback(transaction) => transaction.id(),
+            AccountActivity::Freeze(action)
+            | AccountActivity::Unfreeze(action)
+            | AccountActivity::Unlock(action) => action.id(),
+        }
+    }
+
+    /// Returns the point in time the activity took place at, if known. Only transactions,
+    /// transfers and dispute cases carry a timestamp.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            AccountActivity::Deposit(transaction) | AccountActivity::Withdrawal(transaction) => {
+                transaction.timestamp()
+            }
+            AccountActivity::Transfer(transfer) => transfer.timestamp(),
+            AccountActivity::Dispute(dispute_case)
+            | AccountActivity::Resolve(dispute_case)
+            | AccountActivity::Chargeback(dispute_case) => dispute_case.timestamp(),
+            AccountActivity::Freeze(_)
+            | AccountActivity::Unfreeze(_)
+            | AccountActivity::Unlock(_) => None,
         }
     }
 
+    /// Sets the point in time the activity took place at. Activities that carry no timestamp are
+    /// returned unchanged, see [`AccountActivity::timestamp`].
+    pub fn with_timestamp(self, timestamp: Timestamp) -> Self {
+        match self {
+            AccountActivity::Deposit(transaction) => {
+                AccountActivity::Deposit(transaction.with_timestamp(timestamp))
+            }
+            AccountActivity::Withdrawal(transaction) => {
+                AccountActivity::Withdrawal(transaction.with_timestamp(timestamp))
+            }
+            AccountActivity::Transfer(transfer) => {
+                AccountActivity::Transfer(transfer.with_timestamp(timestamp))
+            }
+            AccountActivity::Dispute(dispute_case) => {
+                AccountActivity::Dispute(dispute_case.with_timestamp(timestamp))
+            }
+            AccountActivity::Resolve(dispute_case) => {
+                AccountActivity::Resolve(dispute_case.with_timestamp(timestamp))
+            }
+            AccountActivity::Chargeback(dispute_case) => {
+                AccountActivity::Chargeback(dispute_case.with_timestamp(timestamp))
+            }
+            activity => activity,
+        }
+    }
+
+    /// Replaces the amount of a transaction with the result of `f`. Dispute cases and admin actions
+    /// carry no amount and are returned unchanged.
+    pub fn try_map_amount<E, F>(self, f: F) -> Result<Self, E>
+    where
+        F: FnOnce(Decimal) -> Result<Decimal, E>,
+    {
+        Ok(match self {
+            AccountActivity::Deposit(transaction) => {
+                AccountActivity::Deposit(transaction.with_amount(f(transaction.amount())?))
+            }
+            AccountActivity::Withdrawal(transaction) => {
+                AccountActivity::Withdrawal(transaction.with_amount(f(transaction.amount())?))
+            }
+            AccountActivity::Transfer(transfer) => {
+                AccountActivity::Transfer(transfer.with_amount(f(transfer.amount())?))
+            }
+            activity => activity,
+        })
+    }
+
     pub fn client_id(&self) -> ClientID {
         match self {
             AccountActivity::Deposit(transaction) => transaction.client_id(),
             AccountActivity::Withdrawal(transaction) => transaction.client_id(),
+            AccountActivity::Transfer(transfer) => transfer.client_id(),
             AccountActivity::Dispute(transaction) => transaction.client_id(),
             AccountActivity::Resolve(transaction) => transaction.client_id(),
             AccountActivity::Chargeback(transaction) => transaction.client_id(),
+            AccountActivity::Freeze(action)
+            | AccountActivity::Unfreeze(action)
+            | AccountActivity::Unlock(action) => action.client_id(),
+        }
+    }
+
+    pub fn kind(&self) -> ActivityKind {
+        match self {
+            AccountActivity::Deposit(_) => ActivityKind::Deposit,
+            AccountActivity::Withdrawal(_) => ActivityKind::Withdrawal,
+            AccountActivity::Transfer(_) => ActivityKind::Transfer,
+            AccountActivity::Dispute(_) => ActivityKind::Dispute,
+            AccountActivity::Resolve(_) => ActivityKind::Resolve,
+            AccountActivity::Chargeback(_) => ActivityKind::Chargeback,
+            AccountActivity::Freeze(_) => ActivityKind::Freeze,
+            AccountActivity::Unfreeze(_) => ActivityKind::Unfreeze,
+            AccountActivity::Unlock(_) => ActivityKind::Unlock,
         }
     }
 }
 
 impl Display for AccountActivity {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let kind = match self {
-            AccountActivity::Deposit(_) => "deposit",
-            AccountActivity::Withdrawal(_) => "withdrawal",
-            AccountActivity::Dispute(_) => "dispute",
-            AccountActivity::Resolve(_) => "resolve",
-            AccountActivity::Chargeback(_) => "chargeback",
-        };
-        write!(f, "{}", kind)
+        write!(f, "{}", self.kind())
     }
 }
@@ -0,0 +1,49 @@
+use crate::transaction::TransactionID;
+use crate::ClientID;
+
+/// An administrative action taken by support staff on the account of a client, e.g. freezing a
+/// suspicious account or reinstating an account after a chargeback.
+///
+/// Every action states the reason it was taken for and optionally the operator who took it, both of
+/// which are recorded in the account.
+#[derive(serde::Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct AdminAction {
+    #[serde(rename = "tx")]
+    id: TransactionID,
+
+    #[serde(rename = "client")]
+    client_id: ClientID,
+
+    reason: String,
+
+    #[serde(default)]
+    operator: Option<String>,
+}
+
+impl AdminAction {
+    pub fn new(id: TransactionID, client_id: ClientID, reason: impl Into<String>) -> Self {
+        Self { id, client_id, reason: reason.into(), operator: None }
+    }
+
+    /// Sets the operator who took the action.
+    pub fn with_operator(mut self, operator: impl Into<String>) -> Self {
+        self.operator = Some(operator.into());
+        self
+    }
+
+    pub fn id(&self) -> TransactionID {
+        self.id
+    }
+
+    pub fn client_id(&self) -> ClientID {
+        self.client_id
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+}
@@ -0,0 +1,179 @@
+use crate::account_activity::AccountActivity;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum AmountError {
+    /// Indicates that an amount has more fractional digits than permitted by the [`AmountPolicy`].
+    #[error("amount {amount} exceeds the maximum of {scale} fractional digits")]
+    ExcessPrecision { amount: Decimal, scale: u32 },
+}
+
+/// The strategy used to round amounts to the scale of an [`AmountPolicy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Rounds half-way values to the nearest even number, also known as banker's rounding.
+    #[default]
+    Bankers,
+
+    /// Rounds half-way values away from zero.
+    HalfUp,
+
+    /// Discards any excess fractional digits.
+    Truncate,
+}
+
+impl From<Rounding> for rust_decimal::RoundingStrategy {
+    fn from(rounding: Rounding) -> Self {
+        match rounding {
+            Rounding::Bankers => rust_decimal::RoundingStrategy::MidpointNearestEven,
+            Rounding::HalfUp => rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+            Rounding::Truncate => rust_decimal::RoundingStrategy::ToZero,
+        }
+    }
+}
+
+/// Defines the precision amounts are handled with.
+///
+/// Input amounts with more fractional digits than the configured scale are either rounded or
+/// rejected, output amounts are normalized to exactly the configured number of fractional digits.
+///
+/// The policy is configured at runtime, whereas serde's `Deserialize` cannot be given any state
+/// and the csv crate offers no way to deserialize records with a `DeserializeSeed`. Amounts are
+/// therefore deserialized as they appear in the input, and the processors apply the policy to
+/// every activity right after reading it. Callers that deserialize activities themselves, e.g.
+/// for an [`Engine`](crate::engine::Engine), apply it with [`AmountPolicy::apply_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountPolicy {
+    scale: u32,
+    rounding: Rounding,
+    reject_excess_precision: bool,
+}
+
+impl Default for AmountPolicy {
+    /// Four fractional digits, rounded with banker's rounding.
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+impl AmountPolicy {
+    /// Creates a policy with the given number of fractional digits that rounds excess digits with
+    /// banker's rounding.
+    pub fn new(scale: u32) -> Self {
+        Self {
+            scale,
+            rounding: Rounding::default(),
+            reject_excess_precision: false,
+        }
+    }
+
+    /// Sets the strategy used to round amounts with excess fractional digits.
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Sets whether input amounts with excess fractional digits are rejected instead of rounded.
+    pub fn with_reject_excess_precision(mut self, reject: bool) -> Self {
+        self.reject_excess_precision = reject;
+        self
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Applies the policy to an input amount, either rounding or rejecting excess fractional
+    /// digits.
+    pub fn apply(&self, amount: Decimal) -> Result<Decimal, AmountError> {
+        if amount.scale() <= self.scale {
+            Ok(amount)
+        } else if self.reject_excess_precision {
+            Err(AmountError::ExcessPrecision { amount, scale: self.scale })
+        } else {
+            Ok(self.round(amount))
+        }
+    }
+
+    /// Applies the policy to the amount of `activity`, if it has one.
+    pub fn apply_to(&self, activity: AccountActivity) -> Result<AccountActivity, AmountError> {
+        activity.try_map_amount(|amount| self.apply(amount))
+    }
+
+    /// Rounds an amount to exactly the number of fractional digits of the policy, e.g. for output.
+    pub fn normalize(&self, amount: Decimal) -> Decimal {
+        let mut amount = self.round(amount);
+        amount.rescale(self.scale);
+        amount
+    }
+
+    fn round(&self, amount: Decimal) -> Decimal {
+        amount.round_dp_with_strategy(self.scale, self.rounding.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn amounts_within_scale_are_accepted() {
+        let policy = AmountPolicy::new(4).with_reject_excess_precision(true);
+        for amount in [dec!(1), dec!(1.5), dec!(1.2345)] {
+            let result = policy.apply(amount);
+            assert_eq!(result, Ok(amount), "Expected amount to be accepted: {}", amount);
+            assert_eq!(result.map(|amount| amount.scale()), Ok(amount.scale()));
+        }
+    }
+
+    #[test]
+    fn amounts_with_excess_precision_are_rejected() {
+        let policy = AmountPolicy::new(4).with_reject_excess_precision(true);
+        let result = policy.apply(dec!(1.23456));
+        assert_eq!(result, Err(AmountError::ExcessPrecision { amount: dec!(1.23456), scale: 4 }));
+    }
+
+    #[test]
+    fn amounts_with_excess_precision_are_rounded() {
+        let cases = [
+            (Rounding::Bankers, dec!(1.00005), dec!(1.0000)),
+            (Rounding::Bankers, dec!(1.00015), dec!(1.0002)),
+            (Rounding::HalfUp, dec!(1.00005), dec!(1.0001)),
+            (Rounding::HalfUp, dec!(-1.00005), dec!(-1.0001)),
+            (Rounding::Truncate, dec!(1.00009), dec!(1.0000)),
+        ];
+        for (rounding, amount, expected) in cases {
+            let policy = AmountPolicy::new(4).with_rounding(rounding);
+            assert_eq!(policy.apply(amount), Ok(expected),
+                       "Unexpected result rounding {} with {:?}", amount, rounding);
+        }
+    }
+
+    #[test]
+    fn policy_is_applied_to_activities() {
+        let policy = AmountPolicy::new(2);
+        let activity = AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(1.005));
+        assert_eq!(policy.apply_to(activity),
+                   Ok(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(1.00))));
+
+        let activity = AccountActivity::dispute(TransactionID(1), ClientID(1));
+        assert_eq!(policy.apply_to(activity.clone()), Ok(activity));
+    }
+
+    #[test]
+    fn normalized_amounts_have_exact_scale() {
+        let policy = AmountPolicy::new(4);
+        let cases = [
+            (dec!(10), "10.0000"),
+            (dec!(10.00000), "10.0000"),
+            (dec!(0.123456), "0.1235"),
+        ];
+        for (amount, expected) in cases {
+            assert_eq!(policy.normalize(amount).to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Detection of activities that appear out of chronological order.
+//!
+//! Activities are processed in input order by default, regardless of their
+//! [timestamps](AccountActivity::timestamp). An [`OutOfOrderPolicy`] either rejects activities
+//! that precede earlier records of the input or buffers the input to process activities in
+//! chronological order. In both cases, an activity may precede the latest timestamp seen so far by
+//! up to a configurable tolerance.
+use crate::account_activity::AccountActivity;
+use crate::account_activity::AccountActivityError::{self, OutOfOrder};
+use crate::processor::InputRecord;
+use crate::timestamp::Timestamp;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The handling of activities whose timestamp precedes the timestamp of an earlier record.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutOfOrderPolicy {
+    /// Activities are processed in input order, regardless of their timestamps.
+    #[default]
+    Accept,
+
+    /// Activities that precede the latest timestamp seen so far by more than `tolerance` are
+    /// rejected. All other activities are processed in input order.
+    Reject { tolerance: Duration },
+
+    /// Activities are buffered for up to `tolerance` and processed in chronological order.
+    /// Activities that precede the latest timestamp seen so far by more than `tolerance` cannot be
+    /// put in order anymore and are rejected.
+    ///
+    /// Activities without a timestamp are put after all activities seen before them.
+    Reorder { tolerance: Duration },
+}
+
+/// A record admitted by [`Chronological`].
+pub(crate) enum Admitted<E> {
+    /// A record that is to be processed.
+    Record(InputRecord<Result<AccountActivity, E>>),
+
+    /// An activity that appeared out of chronological order, alongside the reason it was
+    /// rejected for.
+    Rejected(InputRecord<AccountActivity>, AccountActivityError),
+}
+
+/// Applies an [`OutOfOrderPolicy`] to the records of the input.
+pub(crate) struct Chronological<I, E> {
+    records: I,
+    policy: OutOfOrderPolicy,
+    latest: Option<Timestamp>,
+    /// The buffered records of the [`Reorder`](OutOfOrderPolicy::Reorder) policy, ordered by
+    /// their timestamp and their position in the input.
+    buffer: BTreeMap<(Timestamp, usize), InputRecord<Result<AccountActivity, E>>>,
+    sequence: usize,
+}
+
+impl<I, R, E> Chronological<I, E>
+where
+    I: Iterator<Item=R>,
+    R: Into<InputRecord<Result<AccountActivity, E>>>,
+{
+    pub(crate) fn new(records: I, policy: OutOfOrderPolicy) -> Self {
+        Self { records, policy, latest: None, buffer: BTreeMap::new(), sequence: 0 }
+    }
+
+    /// Returns whether a timestamp precedes the latest timestamp by more than `tolerance`.
+    fn is_late(&self, timestamp: Timestamp, tolerance: Duration) -> bool {
+        self.latest.is_some_and(|latest| timestamp.saturating_add(tolerance) < latest)
+    }
+
+    /// Checks the timestamp of an activity and advances the latest timestamp.
+    fn admit(
+        &mut self,
+        timestamp: Option<Timestamp>,
+        tolerance: Duration,
+    ) -> Result<(), AccountActivityError> {
+        let Some(timestamp) = timestamp else {
+            return Ok(());
+        };
+        match self.latest {
+            Some(latest) if self.is_late(timestamp, tolerance) => {
+                Err(OutOfOrder { timestamp, latest })
+            }
+            _ => {
+                self.latest = self.latest.max(Some(timestamp));
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes the earliest buffered record if no record that is yet to be read can precede it.
+    fn pop_settled(&mut self, tolerance: Duration) -> Option<Admitted<E>> {
+        let (&(timestamp, _), _) = self.buffer.first_key_value()?;
+        if !self.is_late(timestamp, tolerance) {
+            return None;
+        }
+        self.buffer.pop_first().map(|(_, record)| Admitted::Record(record))
+    }
+}
+
+impl<I, R, E> Iterator for Chronological<I, E>
+where
+    I: Iterator<Item=R>,
+    R: Into<InputRecord<Result<AccountActivity, E>>>,
+{
+    type Item = Admitted<E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tolerance, reorder) = match self.policy {
+            OutOfOrderPolicy::Accept => {
+                return self.records.next().map(|record| Admitted::Record(record.into()));
+            }
+            OutOfOrderPolicy::Reject { tolerance } => (tolerance, false),
+            OutOfOrderPolicy::Reorder { tolerance } => (tolerance, true),
+        };
+        loop {
+            if let Some(admitted) = self.pop_settled(tolerance) {
+                return Some(admitted);
+            }
+            let Some(record) = self.records.next() else {
+                // All records have been read, so the buffered records are settled
+                return self.buffer.pop_first().map(|(_, record)| Admitted::Record(record));
+            };
+            let InputRecord { source, line, raw, value } = record.into();
+            let activity = match value {
+                Ok(activity) => activity,
+                // Records that could not be parsed are reported right away
+                Err(err) => {
+                    let record = InputRecord { source, line, raw, value: Err(err) };
+                    return Some(Admitted::Record(record));
+                }
+            };
+            let timestamp = activity.timestamp();
+            if let Err(err) = self.admit(timestamp, tolerance) {
+                let record = InputRecord { source, line, raw, value: activity };
+                return Some(Admitted::Rejected(record, err));
+            }
+            let record = InputRecord { source, line, raw, value: Ok(activity) };
+            if !reorder {
+                return Some(Admitted::Record(record));
+            }
+            let key = timestamp.or(self.latest).unwrap_or_default();
+            self.buffer.insert((key, self.sequence), record);
+            self.sequence += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Admitted, Chronological, OutOfOrderPolicy};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::OutOfOrder;
+    use crate::timestamp::Timestamp;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    #[error("error parsing account activity record")]
+    struct ParseError;
+
+    const TOLERANCE: Duration = Duration::from_secs(10);
+
+    fn deposit(tx: u32, timestamp: u64) -> Result<AccountActivity, ParseError> {
+        let deposit = AccountActivity::deposit(TransactionID(tx), ClientID(1), dec!(1.0));
+        Ok(deposit.with_timestamp(Timestamp(timestamp)))
+    }
+
+    /// Returns the transaction IDs of the admitted activities, `None` for records that could not
+    /// be parsed, and the IDs of the rejected activities.
+    fn admit(
+        records: Vec<Result<AccountActivity, ParseError>>,
+        policy: OutOfOrderPolicy,
+    ) -> (Vec<Option<u32>>, Vec<u32>) {
+        let mut admitted = Vec::new();
+        let mut rejected = Vec::new();
+        for record in Chronological::new(records.into_iter(), policy) {
+            match record {
+                Admitted::Record(record) => {
+                    admitted.push(record.value.ok().map(|activity| activity.transaction_id().0));
+                }
+                Admitted::Rejected(record, err) => {
+                    assert!(matches!(err, OutOfOrder { .. }), "Unexpected error: {:?}", err);
+                    rejected.push(record.value.transaction_id().0);
+                }
+            }
+        }
+        (admitted, rejected)
+    }
+
+    #[test]
+    fn activities_are_accepted_in_input_order_by_default() {
+        let records = vec![deposit(1, 100), deposit(2, 50), deposit(3, 75)];
+        let (admitted, rejected) = admit(records, OutOfOrderPolicy::default());
+        assert_eq!(admitted, vec![Some(1), Some(2), Some(3)]);
+        assert!(rejected.is_empty(), "Unexpected rejections: {:?}", rejected);
+    }
+
+    #[test]
+    fn late_activities_are_rejected() {
+        let records = vec![deposit(1, 100), deposit(2, 91), deposit(3, 89), deposit(4, 120)];
+        let policy = OutOfOrderPolicy::Reject { tolerance: TOLERANCE };
+        let (admitted, rejected) = admit(records, policy);
+        assert_eq!(admitted, vec![Some(1), Some(2), Some(4)]);
+        assert_eq!(rejected, vec![3]);
+    }
+
+    #[test]
+    fn activities_are_reordered_within_tolerance() {
+        let records = vec![
+            deposit(1, 100),
+            deposit(2, 95),
+            Err(ParseError),
+            Ok(AccountActivity::unlock(TransactionID(3), ClientID(1), "untimestamped")),
+            deposit(4, 120),
+            deposit(5, 112),
+            deposit(6, 100),
+            deposit(7, 130),
+        ];
+        let policy = OutOfOrderPolicy::Reorder { tolerance: TOLERANCE };
+        let (admitted, rejected) = admit(records, policy);
+        assert_eq!(admitted, vec![None, Some(2), Some(1), Some(3), Some(5), Some(4), Some(7)],
+                   "Expected activities to be ordered by timestamp, then by input order");
+        assert_eq!(rejected, vec![6]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::timestamp::Timestamp;
 use crate::transaction::TransactionID;
 use crate::ClientID;
 
@@ -22,11 +23,21 @@ pub struct DisputeCase {
 
     #[serde(rename = "client")]
     client_id: ClientID,
+
+    /// The point in time the dispute case step took place at, if known.
+    #[serde(default)]
+    timestamp: Option<Timestamp>,
 }
 
 impl DisputeCase {
     pub fn new(id: TransactionID, client_id: ClientID) -> Self {
-        Self { transaction_id: id, client_id }
+        Self { transaction_id: id, client_id, timestamp: None }
+    }
+
+    /// Sets the point in time the dispute case step took place at.
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
     }
 
     pub fn id(&self) -> TransactionID {
@@ -36,4 +47,8 @@ impl DisputeCase {
     pub fn client_id(&self) -> ClientID {
         self.client_id
     }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
@@ -0,0 +1,263 @@
+//! Incremental processing of account activities.
+//!
+//! The [`Engine`] applies activities one at a time and keeps the resulting accounts in memory, so
+//! the processor can be embedded in services that receive activities as they happen rather than
+//! reading them from a file. Unlike [`process_activities`](crate::processor::process_activities),
+//! it does not read input records or report rejections; the [`Outcome`] of every activity is
+//! returned to the caller instead.
+//!
+//! Amounts are applied as given. An [`AmountPolicy`](crate::amount::AmountPolicy) is applied to
+//! activities before passing them to the engine with
+//! [`AmountPolicy::apply_to`](crate::amount::AmountPolicy::apply_to).
+use crate::account::{Account, AccountOptions, ActivityEffect};
+use crate::account_activity::AccountActivityError::{self, Storage};
+use crate::account_activity::AccountActivity;
+use crate::processor::{apply_activity, concerned_clients, new_account};
+use crate::registry::{Owners, TransactionRegistry};
+use crate::storage::StorageResult;
+use crate::ClientID;
+use std::collections::HashMap;
+use std::mem;
+
+/// The result of applying a single activity with [`Engine::apply`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    /// The activity has been accepted by the accounts it concerns, with the given effect on each
+    /// of them. A [transfer](AccountActivity::Transfer) concerns both the source and the
+    /// destination account.
+    Applied(Vec<ActivityEffect>),
+
+    /// The activity has been rejected and has not moved any funds. It may still have created the
+    /// account of its client and recorded its transaction as
+    /// [failed](crate::account::TransactionState::Failed), so the transaction ID stays taken.
+    Rejected(AccountActivityError),
+}
+
+impl Outcome {
+    pub fn is_applied(&self) -> bool {
+        matches!(self, Outcome::Applied(_))
+    }
+}
+
+/// Applies account activities one at a time and holds the resulting accounts.
+///
+/// Activities are applied in the order in which they are passed to [`Engine::apply`]. Transaction
+/// IDs must be unique across all clients, see [`TransactionRegistry`].
+#[derive(Debug, Default)]
+pub struct Engine {
+    options: AccountOptions,
+    registry: TransactionRegistry,
+    accounts: HashMap<ClientID, Account>,
+    /// The client IDs of all accounts in the order in which they were first seen.
+    order: Vec<ClientID>,
+}
+
+impl Engine {
+    /// Creates an engine without any accounts. New accounts are configured with `options`.
+    pub fn new(options: AccountOptions) -> Self {
+        let registry = TransactionRegistry::with_options(&options);
+        Self { options, registry, ..Self::default() }
+    }
+
+    /// Creates an engine that continues processing on top of existing `accounts`, e.g. restored
+    /// from a [snapshot](crate::snapshot).
+    ///
+    /// All accounts are [configured](Account::configure) with `options`.
+    pub fn from_accounts(accounts: Vec<Account>, options: AccountOptions) -> StorageResult<Self> {
+        let order = accounts.iter().map(Account::client_id).collect();
+        let registry = TransactionRegistry::with_options(&options).with_accounts(&accounts)?;
+        let accounts = accounts
+            .into_iter()
+            .map(|mut account| {
+                account.configure(options.clone())?;
+                Ok((account.client_id(), account))
+            })
+            .collect::<StorageResult<_>>()?;
+        Ok(Self { options, registry, accounts, order })
+    }
+
+    /// Applies `activity` to the accounts it concerns, creating accounts of clients that have not
+    /// been seen before.
+    ///
+    /// Returns an error if the transaction record of an account could not be accessed. Such
+    /// failures are not caused by the activity, and the state of the accounts is unspecified
+    /// afterwards.
+    pub fn apply(&mut self, activity: AccountActivity) -> StorageResult<Outcome> {
+        let Self { options, registry, accounts, order } = self;
+        let mut clients = concerned_clients(&activity);
+        clients.retain(|client_id| !accounts.contains_key(client_id));
+        let result = match registry.check(&activity) {
+            Ok(()) => {
+                let create = |client_id| new_account(client_id, options);
+                let result = apply_activity(accounts, activity.clone(), create);
+                let account = accounts.get(&activity.client_id());
+                if let Some(owners) = Owners::recorded(&activity, result.is_ok(), account)? {
+                    registry.register(activity.transaction_id(), owners)?;
+                }
+                result
+            }
+            Err(err) => Err(err),
+        };
+        order.extend(clients.into_iter().filter(|client_id| accounts.contains_key(client_id)));
+        match result {
+            Ok(effects) => Ok(Outcome::Applied(effects)),
+            Err(Storage(failure)) => Err(failure.into()),
+            Err(err) => Ok(Outcome::Rejected(err)),
+        }
+    }
+
+    /// Returns the account of a client, if any activity of the client has been applied.
+    pub fn account(&self, client_id: ClientID) -> Option<&Account> {
+        self.accounts.get(&client_id)
+    }
+
+    /// Returns all accounts in the order in which the first activity of each client was applied.
+    pub fn accounts(&self) -> impl Iterator<Item=&Account> {
+        self.order.iter().filter_map(|client_id| self.accounts.get(client_id))
+    }
+
+    /// Returns the number of accounts.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Removes and returns all accounts in the order of [`Engine::accounts`].
+    ///
+    /// The engine starts over afterwards, as if it had been newly created with the same options.
+    /// In particular, it forgets all transaction IDs seen so far.
+    pub fn drain(&mut self) -> Vec<Account> {
+        self.registry = TransactionRegistry::with_options(&self.options);
+        let mut accounts = mem::take(&mut self.accounts);
+        mem::take(&mut self.order)
+            .into_iter()
+            .filter_map(|client_id| accounts.remove(&client_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Engine, Outcome};
+    use crate::account::{Account, AccountOptions, Balances};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{DuplicateTransaction, InsufficientFunds};
+    use crate::snapshot;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn activities_are_applied_one_at_a_time() {
+        let mut engine = Engine::default();
+        let outcome = engine
+            .apply(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(5.0)))
+            .expect("Expected deposit to be applied");
+        let after =
+            Balances { available: dec!(5.0), held: dec!(0.0), total: dec!(5.0), locked: false };
+        assert!(matches!(&outcome, Outcome::Applied(effects) if effects[0].after() == after),
+                "Expected deposit to be applied: {:?}", outcome);
+        let account = engine.account(ClientID(1)).expect("Expected account to be created");
+        assert_eq!(account.available(), dec!(5.0));
+
+        let outcome = engine
+            .apply(AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(7.0)))
+            .expect("Expected withdrawal to be processed");
+        assert_eq!(
+            outcome,
+            Outcome::Rejected(InsufficientFunds { requested: dec!(7.0), available: dec!(5.0) }),
+        );
+        let account = engine.account(ClientID(1)).expect("Expected account to be kept");
+        assert_eq!(account.available(), dec!(5.0), "Expected rejected withdrawal to be skipped");
+    }
+
+    #[test]
+    fn transfers_create_both_accounts_in_order() {
+        let mut engine = Engine::default();
+        for activity in [
+            AccountActivity::deposit(TransactionID(1), ClientID(2), dec!(5.0)),
+            AccountActivity::transfer(TransactionID(2), ClientID(2), ClientID(1), dec!(2.0)),
+        ] {
+            let outcome = engine.apply(activity).expect("Test setup: activity failed");
+            assert!(outcome.is_applied(), "Test setup: activity rejected: {:?}", outcome);
+        }
+        let accounts = engine
+            .accounts()
+            .map(|account| (account.client_id(), account.available()))
+            .collect::<Vec<_>>();
+        assert_eq!(accounts, vec![(ClientID(2), dec!(3.0)), (ClientID(1), dec!(2.0))]);
+    }
+
+    #[test]
+    fn existing_accounts_keep_their_transactions() {
+        let mut existing = Account::new(ClientID(1));
+        existing
+            .transaction(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(5.0)))
+            .expect("Test setup: deposit failed");
+        let mut engine = Engine::from_accounts(vec![existing], AccountOptions::default())
+            .expect("Expected engine to be created");
+
+        let outcome = engine
+            .apply(AccountActivity::deposit(TransactionID(1), ClientID(2), dec!(1.0)))
+            .expect("Expected deposit to be processed");
+        assert_eq!(outcome, Outcome::Rejected(DuplicateTransaction(TransactionID(1))));
+    }
+
+    #[test]
+    fn drained_engine_starts_over() {
+        let mut engine = Engine::default();
+        let deposit = AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(5.0));
+        engine.apply(deposit.clone()).expect("Test setup: deposit failed");
+
+        let accounts = engine.drain();
+        assert_eq!(accounts.len(), 1, "Expected drained accounts to be returned");
+        assert!(engine.is_empty(), "Expected engine to be empty after draining");
+
+        let outcome = engine.apply(deposit).expect("Expected deposit to be processed");
+        assert!(outcome.is_applied(), "Expected transaction IDs to be forgotten: {:?}", outcome);
+    }
+
+    #[test]
+    fn transaction_ids_stay_unique_across_snapshots() {
+        let first_run = || {
+            let mut engine = Engine::default();
+            for activity in [
+                AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)),
+                // Rejected for lack of funds, but recorded as failed
+                AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(100.0)),
+                AccountActivity::transfer(TransactionID(3), ClientID(1), ClientID(2), dec!(100.0)),
+                // Rejected without being recorded
+                AccountActivity::transfer(TransactionID(4), ClientID(1), ClientID(1), dec!(1.0)),
+                AccountActivity::deposit(TransactionID(5), ClientID(3), dec!(1.0)),
+                AccountActivity::dispute(TransactionID(5), ClientID(3)),
+                AccountActivity::chargeback(TransactionID(5), ClientID(3)),
+                AccountActivity::deposit(TransactionID(6), ClientID(3), dec!(1.0)),
+            ] {
+                engine.apply(activity).expect("Test setup: activity failed");
+            }
+            engine
+        };
+        let second_run = |engine: &mut Engine| {
+            (2..=6)
+                .map(|tx| AccountActivity::deposit(TransactionID(tx), ClientID(2), dec!(1.0)))
+                .map(|deposit| engine.apply(deposit).expect("Expected deposit to be processed"))
+                .collect::<Vec<_>>()
+        };
+
+        let mut buffer = Vec::new();
+        snapshot::write(&mut buffer, &first_run().drain()).expect("Test setup: write failed");
+        let accounts = snapshot::read(buffer.as_slice()).expect("Test setup: read failed");
+        let mut restored = Engine::from_accounts(accounts, AccountOptions::default())
+            .expect("Expected engine to be restored");
+        let outcomes = second_run(&mut restored);
+
+        assert_eq!(outcomes, second_run(&mut first_run()),
+                   "Expected same outcomes with and without snapshot");
+        let applied = outcomes.iter().map(Outcome::is_applied).collect::<Vec<_>>();
+        assert_eq!(applied, vec![false, false, true, false, true],
+                   "Expected only transaction IDs of rejected activities to be reusable");
+    }
+}
@@ -0,0 +1,228 @@
+//! A stream of the outcomes of all processed activities.
+//!
+//! Unlike [rejections](crate::rejection), which only cover the records that have been dropped,
+//! an [`ActivityEvent`] is emitted for every parsed activity. Events of applied activities
+//! describe what the activity changed about the accounts it concerns, one event per account.
+use crate::account::{ActivityEffect, Balances, EffectStatus, LockChange};
+use crate::account_activity::{AccountActivity, AccountActivityError};
+use crate::transaction::TransactionID;
+use crate::ClientID;
+use serde::Serialize;
+use std::io;
+
+/// The outcome of an activity as reported by an [`ActivityEvent`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventOutcome {
+    /// The activity has been applied to the account.
+    Applied,
+
+    /// The activity references an unknown transaction and has been ignored.
+    Ignored,
+
+    /// The activity has been rejected.
+    Rejected,
+}
+
+/// The outcome of a single activity for a single account.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ActivityEvent {
+    /// The name of the input the activity was read from, if known.
+    source: Option<String>,
+
+    /// The line of the input the activity starts on, if known.
+    line: Option<u64>,
+
+    /// The kind of the account activity.
+    activity: &'static str,
+
+    client: ClientID,
+
+    tx: TransactionID,
+
+    outcome: EventOutcome,
+
+    /// A short, stable identifier of the reason a rejected activity was rejected for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+
+    /// The balances of the account before an accepted activity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<Balances>,
+
+    /// The balances of the account after an accepted activity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<Balances>,
+
+    /// Whether the activity locked or unlocked the account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lock: Option<LockChange>,
+}
+
+impl ActivityEvent {
+    /// Creates the event of an activity that has been accepted by the account of
+    /// [`ActivityEffect::client_id`].
+    pub fn accepted(
+        line: Option<u64>,
+        activity: &AccountActivity,
+        effect: &ActivityEffect,
+    ) -> Self {
+        let outcome = match effect.status() {
+            EffectStatus::Applied => EventOutcome::Applied,
+            EffectStatus::UnknownTransaction => EventOutcome::Ignored,
+        };
+        Self {
+            source: None,
+            line,
+            activity: activity.kind().as_str(),
+            client: effect.client_id(),
+            tx: activity.transaction_id(),
+            outcome,
+            error: None,
+            before: Some(effect.before()),
+            after: Some(effect.after()),
+            lock: effect.lock_change(),
+        }
+    }
+
+    /// Creates the event of an activity that has been rejected.
+    pub fn rejected(
+        line: Option<u64>,
+        activity: &AccountActivity,
+        error: &AccountActivityError,
+    ) -> Self {
+        Self {
+            source: None,
+            line,
+            activity: activity.kind().as_str(),
+            client: activity.client_id(),
+            tx: activity.transaction_id(),
+            outcome: EventOutcome::Rejected,
+            error: Some(error.code()),
+            before: None,
+            after: None,
+            lock: None,
+        }
+    }
+
+    /// Sets the name of the input the activity was read from.
+    pub fn with_source(mut self, source: Option<&str>) -> Self {
+        self.source = source.map(str::to_string);
+        self
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn line(&self) -> Option<u64> {
+        self.line
+    }
+
+    pub fn client_id(&self) -> ClientID {
+        self.client
+    }
+
+    pub fn transaction_id(&self) -> TransactionID {
+        self.tx
+    }
+
+    pub fn outcome(&self) -> EventOutcome {
+        self.outcome
+    }
+
+    pub fn error_code(&self) -> Option<&'static str> {
+        self.error
+    }
+
+    pub fn before(&self) -> Option<Balances> {
+        self.before
+    }
+
+    pub fn after(&self) -> Option<Balances> {
+        self.after
+    }
+
+    pub fn lock_change(&self) -> Option<LockChange> {
+        self.lock
+    }
+}
+
+/// A destination for [`ActivityEvent`]s emitted during processing.
+pub trait EventSink {
+    /// Reports a single event.
+    fn emit(&mut self, event: ActivityEvent) -> io::Result<()>;
+
+    /// Flushes any buffered events. Called once processing has finished.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl EventSink for Vec<ActivityEvent> {
+    fn emit(&mut self, event: ActivityEvent) -> io::Result<()> {
+        self.push(event);
+        Ok(())
+    }
+}
+
+/// An [`EventSink`] that writes events as JSON objects, one per line.
+pub struct JsonEventWriter<W: io::Write> {
+    writer: io::BufWriter<W>,
+}
+
+impl<W: io::Write> JsonEventWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: io::BufWriter::new(writer) }
+    }
+}
+
+impl<W: io::Write> EventSink for JsonEventWriter<W> {
+    fn emit(&mut self, event: ActivityEvent) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, &event)?;
+        io::Write::write_all(&mut self.writer, b"\n")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(&mut self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Account;
+    use crate::account_activity::AccountActivityError::InsufficientFunds;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn events_are_written_as_json() {
+        let mut account = Account::new(ClientID(1));
+        let deposit = AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0));
+        let effect = account.transaction(deposit.clone()).expect("Test setup: deposit failed");
+        let withdrawal = AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(15.0));
+        let error = InsufficientFunds { requested: dec!(15.0), available: dec!(10.0) };
+
+        let mut output = Vec::new();
+        let mut sink = JsonEventWriter::new(&mut output);
+        sink.emit(ActivityEvent::accepted(Some(2), &deposit, &effect).with_source(Some("a.csv")))
+            .expect("Expected event to be written");
+        sink.emit(ActivityEvent::rejected(Some(3), &withdrawal, &error))
+            .expect("Expected event to be written");
+        sink.flush().expect("Expected events to be flushed");
+        drop(sink);
+
+        let output = String::from_utf8(output).expect("Expected output to be UTF-8");
+        let expected = concat!(
+            r#"{"source":"a.csv","line":2,"activity":"deposit","client":1,"tx":1,"#,
+            r#""outcome":"applied","before":{"available":"0.0","held":"0.0","total":"0.0","#,
+            r#""locked":false},"after":{"available":"10.0","held":"0.0","total":"10.0","#,
+            r#""locked":false}}"#,
+            "\n",
+            r#"{"source":null,"line":3,"activity":"withdrawal","client":1,"tx":2,"#,
+            r#""outcome":"rejected","error":"insufficient_funds"}"#,
+            "\n",
+        );
+        assert_eq!(output, expected);
+    }
+}
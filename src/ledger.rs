@@ -0,0 +1,240 @@
+//! An append-only record of the balance movements of an [`Account`].
+//!
+//! Every change of the balances of an account is recorded as a movement of an amount from one
+//! [`Bucket`] to another, alongside the activity that caused it and the resulting balances. Funds
+//! entering or leaving the account are moved from or to the [`External`](Bucket::External) bucket,
+//! so the sum of the movements of an account always explains its final balances.
+//!
+//! The ledger is opt-in, see [`AccountOptions::with_ledger`](crate::account::AccountOptions).
+use crate::account::{Account, AccountBalances};
+use crate::account_activity::AccountActivity;
+use crate::transaction::TransactionID;
+use crate::ClientID;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::io;
+
+/// A balance of an account that funds are moved between.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    /// Funds outside of the account, e.g. the source of a deposit or the target of a withdrawal.
+    External,
+
+    /// The available funds of the account.
+    Available,
+
+    /// The held funds of the account.
+    Held,
+}
+
+/// A single movement of funds between two [`Bucket`]s.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct LedgerEntry {
+    client: ClientID,
+
+    tx: TransactionID,
+
+    /// The kind of the activity that caused the movement.
+    activity: &'static str,
+
+    /// The bucket the funds were taken from.
+    debit: Bucket,
+
+    /// The bucket the funds were moved to.
+    credit: Bucket,
+
+    amount: Decimal,
+
+    /// The available funds after the movement.
+    available: Decimal,
+
+    /// The held funds after the movement.
+    held: Decimal,
+
+    /// The total funds after the movement.
+    total: Decimal,
+}
+
+impl LedgerEntry {
+    pub fn client_id(&self) -> ClientID {
+        self.client
+    }
+
+    pub fn transaction_id(&self) -> TransactionID {
+        self.tx
+    }
+
+    pub fn activity(&self) -> &'static str {
+        self.activity
+    }
+
+    pub fn debit(&self) -> Bucket {
+        self.debit
+    }
+
+    pub fn credit(&self) -> Bucket {
+        self.credit
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn available(&self) -> Decimal {
+        self.available
+    }
+
+    pub fn held(&self) -> Decimal {
+        self.held
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.total
+    }
+}
+
+/// The movements of a single account, in the order in which they happened.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Ledger {
+    entries: Vec<LedgerEntry>,
+
+    /// The transaction ID and kind of the activity that is currently being applied to the account.
+    activity: Option<(TransactionID, &'static str)>,
+}
+
+impl Ledger {
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// Sets the activity subsequent movements are attributed to.
+    pub(crate) fn begin(&mut self, activity: &AccountActivity) {
+        self.activity = Some((activity.transaction_id(), activity.kind().as_str()));
+    }
+
+    /// Records a movement of `amount` from `debit` to `credit` that resulted in `balances`.
+    ///
+    /// Movements outside of an activity are not recorded.
+    pub(crate) fn post(
+        &mut self,
+        balances: &AccountBalances,
+        debit: Bucket,
+        credit: Bucket,
+        amount: Decimal,
+    ) {
+        let Some((tx, activity)) = self.activity else {
+            return;
+        };
+        self.entries.push(LedgerEntry {
+            client: balances.client_id(),
+            tx,
+            activity,
+            debit,
+            credit,
+            amount,
+            available: balances.available(),
+            held: balances.held(),
+            total: balances.total(),
+        });
+    }
+}
+
+/// Writes the ledger entries of the given accounts as CSV records, including a header line.
+///
+/// Entries are grouped by account in the order of `accounts`. Accounts without a ledger are
+/// skipped.
+pub fn write_csv<W: io::Write>(writer: W, accounts: &[Account]) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let entries = accounts
+        .iter()
+        .filter_map(Account::ledger)
+        .flat_map(Ledger::entries);
+    for entry in entries {
+        writer.serialize(entry)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::AccountOptions;
+    use rust_decimal_macros::dec;
+
+    fn account() -> Account {
+        let client_id = ClientID(1);
+        let options = AccountOptions::default().with_ledger(true);
+        Account::with_options(client_id, options).with_activities([
+            AccountActivity::deposit(TransactionID(1), client_id, dec!(100.0)),
+            AccountActivity::withdrawal(TransactionID(2), client_id, dec!(30.0)),
+            AccountActivity::dispute(TransactionID(1), client_id),
+            AccountActivity::resolve(TransactionID(1), client_id),
+            AccountActivity::deposit(TransactionID(3), client_id, dec!(5.0)),
+            AccountActivity::dispute(TransactionID(3), client_id),
+            AccountActivity::chargeback(TransactionID(3), client_id),
+        ])
+    }
+
+    #[test]
+    fn every_movement_is_recorded() {
+        let account = account();
+        let entries = account.ledger().expect("Expected ledger to be enabled").entries();
+
+        let movements = entries
+            .iter()
+            .map(|entry| (entry.transaction_id(), entry.activity(), entry.debit(), entry.credit()))
+            .collect::<Vec<_>>();
+        assert_eq!(movements, vec![
+            (TransactionID(1), "deposit", Bucket::External, Bucket::Available),
+            (TransactionID(2), "withdrawal", Bucket::Available, Bucket::External),
+            (TransactionID(1), "dispute", Bucket::Available, Bucket::Held),
+            (TransactionID(1), "resolve", Bucket::Held, Bucket::Available),
+            (TransactionID(3), "deposit", Bucket::External, Bucket::Available),
+            (TransactionID(3), "dispute", Bucket::Available, Bucket::Held),
+            (TransactionID(3), "chargeback", Bucket::Held, Bucket::External),
+        ]);
+
+        let last = entries.last().expect("Expected ledger entries");
+        assert_eq!((last.available(), last.held(), last.total()),
+                   (account.available(), account.held(), account.total()),
+                   "Expected the last entry to hold the final balances");
+    }
+
+    #[test]
+    fn failed_activities_are_not_recorded() {
+        let client_id = ClientID(1);
+        let options = AccountOptions::default().with_ledger(true);
+        let mut account = Account::with_options(client_id, options);
+        let result = account.transaction(
+            AccountActivity::withdrawal(TransactionID(1), client_id, dec!(10.0)),
+        );
+
+        assert!(result.is_err(), "Expected withdrawal to fail");
+        assert_eq!(account.ledger().map(Ledger::entries), Some([].as_slice()));
+    }
+
+    #[test]
+    fn ledger_is_disabled_by_default() {
+        let mut account = Account::new(ClientID(1));
+        account
+            .transaction(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)))
+            .expect("Test setup: deposit failed");
+        assert_eq!(account.ledger(), None);
+    }
+
+    #[test]
+    fn ledger_is_written_as_csv() {
+        let mut output = Vec::new();
+        write_csv(&mut output, &[account()]).expect("Expected ledger to be written");
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        let lines = output.lines().take(3).collect::<Vec<_>>();
+        assert_eq!(lines, vec![
+            "client,tx,activity,debit,credit,amount,available,held,total",
+            "1,1,deposit,external,available,100.0,100.0,0.0,100.0",
+            "1,2,withdrawal,available,external,30.0,70.0,0.0,70.0",
+        ]);
+    }
+}
@@ -10,13 +10,30 @@ use std::hash::Hash;
 
 pub mod account;
 pub mod account_activity;
+pub mod admin;
+pub mod amount;
+pub mod chronology;
 pub mod dispute;
+pub mod engine;
+pub mod events;
+pub mod ledger;
 pub mod processor;
 pub mod processors;
+pub mod registry;
+pub mod rejection;
+pub mod sharded;
+pub mod snapshot;
+pub mod storage;
+pub mod summary;
+pub mod timestamp;
 pub mod transaction;
+pub mod transfer;
 
 /// A globally unique client ID.
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash,
+    Default
+)]
 pub struct ClientID(pub u16);
 
 impl Display for ClientID {
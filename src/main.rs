@@ -1,29 +1,538 @@
 use anyhow::Context;
-use clap::{Parser, ValueHint};
-use payment_processor::processor::Processor;
-use payment_processor::processors::csv::CsvProcessor;
-use std::io::Write;
-use std::{fs::File, io, path::PathBuf};
+use clap::{Parser, ValueEnum, ValueHint};
+use payment_processor::account::{
+    Account, AccountOptions, LockedAccountPolicy, WithdrawalDisputes,
+};
+use payment_processor::account_activity::ActivityKind;
+use payment_processor::amount::{AmountPolicy, Rounding};
+use payment_processor::chronology::OutOfOrderPolicy;
+use payment_processor::events::{EventSink, JsonEventWriter};
+use payment_processor::processor::{AccountOrdering, Processor, Reporting};
+use payment_processor::processors::csv::{
+    ColumnMapping, CsvDialect, CsvOptions, CsvProcessor, Terminator,
+};
+use payment_processor::processors::jsonl::JsonlProcessor;
+use payment_processor::processors::OutputFormat;
+use payment_processor::rejection::{
+    CsvRejectionWriter, DiscardRejections, JsonRejectionWriter, RejectionSink,
+};
+use payment_processor::storage::DiskStorage;
+use payment_processor::summary::Summary;
+use payment_processor::{ledger, snapshot};
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{env, fs, fs::File, io, path::PathBuf};
 use tracing_subscriber::EnvFilter;
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum DataFormat {
+    /// Comma separated values, including a header line.
+    Csv,
+    /// JSON objects, one per line.
+    Jsonl,
+}
+
+impl DataFormat {
+    /// Determines the format of a file by its extension. Files with unknown extensions are
+    /// treated as CSV.
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "ndjson") => DataFormat::Jsonl,
+            _ => DataFormat::Csv,
+        }
+    }
+}
+
+impl From<DataFormat> for OutputFormat {
+    fn from(format: DataFormat) -> Self {
+        match format {
+            DataFormat::Csv => OutputFormat::Csv,
+            DataFormat::Jsonl => OutputFormat::Jsonl,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum LineTerminator {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    Crlf,
+}
+
+impl From<LineTerminator> for Terminator {
+    fn from(terminator: LineTerminator) -> Self {
+        match terminator {
+            LineTerminator::Lf => Terminator::Newline,
+            LineTerminator::Crlf => Terminator::Crlf,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ReportFormat {
+    /// Comma separated values, including a header line.
+    Csv,
+    /// JSON objects, one per line.
+    Json,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SummaryFormat {
+    /// Human readable text.
+    Text,
+    /// A single JSON object.
+    Json,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OrderBy {
+    /// Ascending by client ID.
+    Client,
+    /// In the order of the first activity of each client in the input.
+    FirstActivity,
+    /// Ascending by total balance.
+    Total,
+}
+
+impl From<OrderBy> for AccountOrdering {
+    fn from(order_by: OrderBy) -> Self {
+        match order_by {
+            OrderBy::Client => AccountOrdering::ClientId,
+            OrderBy::FirstActivity => AccountOrdering::FirstActivity,
+            OrderBy::Total => AccountOrdering::TotalBalance,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum RoundingMode {
+    /// Round half-way values to the nearest even number.
+    Bankers,
+    /// Round half-way values away from zero.
+    HalfUp,
+    /// Discard excess fractional digits.
+    Truncate,
+}
+
+impl From<RoundingMode> for Rounding {
+    fn from(mode: RoundingMode) -> Self {
+        match mode {
+            RoundingMode::Bankers => Rounding::Bankers,
+            RoundingMode::HalfUp => Rounding::HalfUp,
+            RoundingMode::Truncate => Rounding::Truncate,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum WithdrawalDisputePolicy {
+    /// Provisionally credit the disputed amount to the held funds.
+    ProvisionalCredit,
+    /// Reject disputes of withdrawals.
+    Reject,
+}
+
+impl From<WithdrawalDisputePolicy> for WithdrawalDisputes {
+    fn from(policy: WithdrawalDisputePolicy) -> Self {
+        match policy {
+            WithdrawalDisputePolicy::ProvisionalCredit => WithdrawalDisputes::ProvisionalCredit,
+            WithdrawalDisputePolicy::Reject => WithdrawalDisputes::Reject,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum LockedActivity {
+    Deposit,
+    Withdrawal,
+    Transfer,
+    Dispute,
+    Resolve,
+    Chargeback,
+    Freeze,
+}
+
+impl From<LockedActivity> for ActivityKind {
+    fn from(activity: LockedActivity) -> Self {
+        match activity {
+            LockedActivity::Deposit => ActivityKind::Deposit,
+            LockedActivity::Withdrawal => ActivityKind::Withdrawal,
+            LockedActivity::Transfer => ActivityKind::Transfer,
+            LockedActivity::Dispute => ActivityKind::Dispute,
+            LockedActivity::Resolve => ActivityKind::Resolve,
+            LockedActivity::Chargeback => ActivityKind::Chargeback,
+            LockedActivity::Freeze => ActivityKind::Freeze,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OutOfOrder {
+    /// Process activities in input order, regardless of their timestamps.
+    Accept,
+    /// Reject activities that precede an earlier record by more than the tolerance.
+    Reject,
+    /// Process activities in chronological order, buffering them for up to the tolerance.
+    Reorder,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum StorageKind {
+    /// Hold the transaction records in memory.
+    Memory,
+    /// Hold the transaction records in a temporary database on disk.
+    Disk,
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
-    /// Path to a file that holds account activity records.
+    /// Paths to files that hold account activity records, processed in the given order. Use `-`
+    /// to read from stdin.
     ///
-    /// Supported file formats: CSV
-    #[arg(value_hint = ValueHint::FilePath)]
-    path: PathBuf,
+    /// Supported file formats: CSV, JSON Lines
+    #[arg(required = true, value_hint = ValueHint::FilePath)]
+    paths: Vec<PathBuf>,
+
+    /// The format of the input files. Determined by the file extensions if omitted, with `.jsonl`
+    /// and `.ndjson` files read as JSON Lines and any other file, including stdin, as CSV. All
+    /// input files must be of the same format.
+    #[arg(long, value_enum)]
+    input_format: Option<DataFormat>,
+
+    /// Alternative names of the columns of CSV inputs, as comma separated `alias=column` pairs,
+    /// e.g. `kind=type,client_id=client`. Columns are matched by name, in any order.
+    #[arg(long, value_name = "ALIAS=COLUMN,...")]
+    columns: Option<ColumnMapping>,
+
+    /// The character that separates the fields of CSV inputs. Use `\t` for tabs.
+    #[arg(long, value_name = "CHAR", value_parser = parse_char, default_value = ",")]
+    delimiter: u8,
+
+    /// The character that quotes the fields of CSV inputs.
+    #[arg(long, value_name = "CHAR", value_parser = parse_char, default_value = "\"")]
+    quote: u8,
+
+    /// The character that terminates the records of CSV inputs. Records are terminated by any of
+    /// `\r`, `\n` and `\r\n` if omitted.
+    #[arg(long, value_name = "CHAR", value_parser = parse_char)]
+    terminator: Option<u8>,
+
+    /// The character that starts comment lines in CSV inputs, which are skipped.
+    #[arg(long, value_name = "CHAR", value_parser = parse_char)]
+    comment: Option<u8>,
+
+    /// Whether CSV inputs lack a header line. The columns are then expected to be `type`,
+    /// `client`, `tx` and `amount`, in that order.
+    #[clap(long, action)]
+    no_headers: bool,
+
+    /// Whether to keep whitespace around the fields of CSV inputs instead of trimming it.
+    #[clap(long, action)]
+    no_trim: bool,
+
+    /// The format accounts are written to stdout in.
+    #[arg(long, value_enum, default_value_t = DataFormat::Csv)]
+    output_format: DataFormat,
+
+    /// The character that separates the fields of CSV output. Use `\t` for tabs.
+    #[arg(long, value_name = "CHAR", value_parser = parse_char, default_value = ",")]
+    output_delimiter: u8,
+
+    /// The character that quotes the fields of CSV output.
+    #[arg(long, value_name = "CHAR", value_parser = parse_char, default_value = "\"")]
+    output_quote: u8,
+
+    /// The line terminator of CSV output.
+    #[arg(long, value_enum, default_value_t = LineTerminator::Lf)]
+    output_terminator: LineTerminator,
+
+    /// Whether to omit the header line of CSV output.
+    #[clap(long, action)]
+    output_no_headers: bool,
 
     /// Whether to suppress printing the results to stdout.
     #[clap(long, action)]
     silent: bool,
+
+    /// The order in which accounts are written to stdout.
+    #[arg(long, value_enum, default_value_t = OrderBy::Client)]
+    order_by: OrderBy,
+
+    /// The number of worker threads activities are processed on, sharded by client.
+    #[arg(long, default_value = "1")]
+    shards: NonZeroUsize,
+
+    /// The number of fractional digits amounts are handled with.
+    #[arg(long, default_value_t = 4)]
+    scale: u32,
+
+    /// The strategy used to round amounts with excess fractional digits.
+    #[arg(long, value_enum, default_value_t = RoundingMode::Bankers)]
+    rounding: RoundingMode,
+
+    /// Whether to reject input amounts with excess fractional digits instead of rounding them.
+    #[clap(long, action)]
+    reject_excess_precision: bool,
+
+    /// How disputes of withdrawals are handled.
+    #[arg(long, value_enum, default_value_t = WithdrawalDisputePolicy::ProvisionalCredit)]
+    withdrawal_disputes: WithdrawalDisputePolicy,
+
+    /// The kinds of activities locked accounts accept, separated by commas. Locked accounts
+    /// reject all other activities, except for unfreezing and unlocking them.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    locked_accepts: Vec<LockedActivity>,
+
+    /// The number of seconds after a transaction within which the transaction can be disputed.
+    /// Only applies to transactions with a timestamp. Transactions can be disputed at any time if
+    /// omitted.
+    #[arg(long, value_name = "SECONDS")]
+    dispute_window: Option<u64>,
+
+    /// Whether to evict the records of transactions once their dispute window has expired.
+    /// Requires --dispute-window and an --out-of-order policy other than accept, as eviction is
+    /// delayed by the --out-of-order-tolerance so that admitted disputes still find their
+    /// transaction.
+    #[clap(long, action, requires = "dispute_window")]
+    evict_expired: bool,
+
+    /// How activities whose timestamp precedes the timestamp of an earlier record are handled.
+    #[arg(long, value_enum, default_value_t = OutOfOrder::Accept)]
+    out_of_order: OutOfOrder,
+
+    /// The number of seconds an activity may precede the latest timestamp seen so far without
+    /// being considered out of order.
+    #[arg(long, value_name = "SECONDS", default_value_t = 0)]
+    out_of_order_tolerance: u64,
+
+    /// Where the transaction records of the accounts are held. Holding them on disk reduces the
+    /// memory usage for inputs with many transactions, at the cost of throughput. The owners of
+    /// all transaction IDs are held alongside the records.
+    #[arg(long, value_enum, default_value_t = StorageKind::Memory)]
+    storage: StorageKind,
+
+    /// The directory the temporary database is created in when holding transaction records on
+    /// disk. Defaults to the directory for temporary files of the system.
+    #[arg(long, value_hint = ValueHint::DirPath)]
+    storage_dir: Option<PathBuf>,
+
+    /// Path to a snapshot of the account state.
+    ///
+    /// If the file exists, activities are processed on top of the accounts it holds. Afterwards,
+    /// the file is replaced with a snapshot of the resulting accounts.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    state: Option<PathBuf>,
+
+    /// Path to a file that records which could not be processed are written to.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    rejections: Option<PathBuf>,
+
+    /// The format of the rejected records report.
+    #[arg(long, value_enum, default_value_t = ReportFormat::Csv)]
+    rejections_format: ReportFormat,
+
+    /// Path to a CSV file that every balance movement is written to.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    ledger: Option<PathBuf>,
+
+    /// Path to a JSON Lines file that the outcome of every activity is written to, including the
+    /// balances of the affected accounts before and after the activity.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    events: Option<PathBuf>,
+
+    /// Whether to print statistics about the processed records and the resulting accounts to
+    /// stderr.
+    #[arg(long)]
+    summary: bool,
+
+    /// The format of the summary.
+    #[arg(long, value_enum, default_value_t = SummaryFormat::Text)]
+    summary_format: SummaryFormat,
+
+    /// Whether to abort at the first record that could not be parsed or was rejected. Nothing is
+    /// written to stdout, the ledger or the state file if processing is aborted. Cannot be combined
+    /// with --shards, as the workers may already have applied later records.
+    #[arg(long, conflicts_with_all = ["max_errors", "shards"])]
+    strict: bool,
+
+    /// The number of records that may be dropped before processing is aborted. Records are
+    /// dropped without limit if omitted. Cannot be combined with --shards, see --strict.
+    #[arg(long, value_name = "N", conflicts_with = "shards")]
+    max_errors: Option<u64>,
 }
 
 fn output(silent: bool) -> Box<dyn Write> {
     if silent { Box::new(io::sink()) } else { Box::new(io::stdout()) }
 }
 
+fn amount_policy(cli: &Cli) -> AmountPolicy {
+    AmountPolicy::new(cli.scale)
+        .with_rounding(cli.rounding.into())
+        .with_reject_excess_precision(cli.reject_excess_precision)
+}
+
+fn account_options(cli: &Cli) -> Result<AccountOptions, anyhow::Error> {
+    let locked_accounts = cli.locked_accepts
+        .iter()
+        .copied()
+        .map(ActivityKind::from)
+        .collect::<LockedAccountPolicy>();
+    let mut options = AccountOptions::default()
+        .with_ledger(cli.ledger.is_some())
+        .with_withdrawal_disputes(cli.withdrawal_disputes.into())
+        .with_locked_accounts(locked_accounts)
+        .with_expired_eviction(cli.evict_expired)
+        .with_eviction_delay(Duration::from_secs(cli.out_of_order_tolerance));
+    if cli.evict_expired && matches!(cli.out_of_order, OutOfOrder::Accept) {
+        anyhow::bail!("--evict-expired requires --out-of-order reject or reorder");
+    }
+    if let Some(seconds) = cli.dispute_window {
+        options = options.with_dispute_window(Duration::from_secs(seconds));
+    }
+    Ok(match cli.storage {
+        StorageKind::Memory => options,
+        StorageKind::Disk => {
+            let dir = cli.storage_dir.clone().unwrap_or_else(env::temp_dir);
+            let storage = DiskStorage::temporary_in(&dir)
+                .context("unable to create transaction record database")?;
+            options.with_storage(Arc::new(storage))
+        }
+    })
+}
+
+/// Parses a single ASCII character, or `\t` for a tab.
+fn parse_char(value: &str) -> Result<u8, String> {
+    match value.as_bytes() {
+        [byte] if byte.is_ascii() => Ok(*byte),
+        b"\\t" => Ok(b'\t'),
+        _ => Err(format!("expected a single ASCII character, got {}", value)),
+    }
+}
+
+fn csv_options(cli: &Cli) -> CsvOptions {
+    let dialect = CsvDialect::default()
+        .with_delimiter(cli.delimiter)
+        .with_quote(cli.quote)
+        .with_terminator(cli.terminator.map_or(Terminator::Newline, Terminator::Byte))
+        .with_comment(cli.comment)
+        .with_headers(!cli.no_headers)
+        .with_trim(!cli.no_trim);
+    CsvOptions::default()
+        .with_dialect(dialect)
+        .with_columns(cli.columns.clone().unwrap_or_default())
+}
+
+fn output_dialect(cli: &Cli) -> CsvDialect {
+    CsvDialect::default()
+        .with_delimiter(cli.output_delimiter)
+        .with_quote(cli.output_quote)
+        .with_terminator(cli.output_terminator.into())
+        .with_headers(!cli.output_no_headers)
+}
+
+fn out_of_order_policy(cli: &Cli) -> OutOfOrderPolicy {
+    let tolerance = Duration::from_secs(cli.out_of_order_tolerance);
+    match cli.out_of_order {
+        OutOfOrder::Accept => OutOfOrderPolicy::Accept,
+        OutOfOrder::Reject => OutOfOrderPolicy::Reject { tolerance },
+        OutOfOrder::Reorder => OutOfOrderPolicy::Reorder { tolerance },
+    }
+}
+
+/// The path that denotes stdin as input.
+const STDIN: &str = "-";
+
+fn input_format(cli: &Cli) -> Result<DataFormat, anyhow::Error> {
+    if let Some(format) = cli.input_format {
+        return Ok(format);
+    }
+    let mut formats = cli.paths.iter().map(|path| DataFormat::of(path));
+    let format = formats.next().unwrap_or(DataFormat::Csv);
+    if formats.any(|other| other != format) {
+        anyhow::bail!("input files are of different formats, use --input-format to override");
+    }
+    Ok(format)
+}
+
+/// An input file alongside its name.
+type Input = (String, Box<dyn Read>);
+
+fn open_inputs(paths: &[PathBuf]) -> Result<Vec<Input>, anyhow::Error> {
+    paths
+        .iter()
+        .map(|path| {
+            let name = path.display().to_string();
+            let input: Box<dyn Read> = if name == STDIN {
+                Box::new(io::stdin().lock())
+            } else {
+                let file = File::open(path)
+                    .with_context(|| format!("unable to open input file {}", name))?;
+                Box::new(file)
+            };
+            Ok((name, input))
+        })
+        .collect()
+}
+
+fn rejections(cli: &Cli) -> Result<Box<dyn RejectionSink>, anyhow::Error> {
+    let Some(path) = &cli.rejections else {
+        return Ok(Box::new(DiscardRejections));
+    };
+    let file = File::create(path).context("unable to create rejections file")?;
+    Ok(match cli.rejections_format {
+        ReportFormat::Csv => Box::new(CsvRejectionWriter::new(file)),
+        ReportFormat::Json => Box::new(JsonRejectionWriter::new(file)),
+    })
+}
+
+fn events(cli: &Cli) -> Result<Option<Box<dyn EventSink>>, anyhow::Error> {
+    let Some(path) = &cli.events else {
+        return Ok(None);
+    };
+    let file = File::create(path).context("unable to create events file")?;
+    Ok(Some(Box::new(JsonEventWriter::new(file))))
+}
+
+fn load_state(path: &Path) -> Result<Vec<Account>, anyhow::Error> {
+    match File::open(path) {
+        Ok(file) => snapshot::read(file).context("unable to read state file"),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).context("unable to open state file"),
+    }
+}
+
+fn save_state(path: &Path, accounts: &[Account]) -> Result<(), anyhow::Error> {
+    // Write to a temporary file first to not corrupt the existing state if writing fails
+    let temp_path = path.with_extension("tmp");
+    let file = File::create(&temp_path).context("unable to create state file")?;
+    snapshot::write(file, accounts).context("unable to write state file")?;
+    fs::rename(&temp_path, path).context("unable to replace state file")
+}
+
+fn max_errors(cli: &Cli) -> Option<u64> {
+    if cli.strict { Some(0) } else { cli.max_errors }
+}
+
+fn print_summary(summary: &Summary, format: SummaryFormat) -> Result<(), anyhow::Error> {
+    match format {
+        SummaryFormat::Text => eprintln!("{}", summary),
+        SummaryFormat::Json => {
+            eprintln!("{}", serde_json::to_string(summary).context("unable to write summary")?)
+        }
+    }
+    Ok(())
+}
+
+fn write_ledger(path: &Path, accounts: &[Account]) -> Result<(), anyhow::Error> {
+    let file = File::create(path).context("unable to create ledger file")?;
+    ledger::write_csv(file, accounts).context("unable to write ledger file")
+}
+
 fn main() -> Result<(), anyhow::Error> {
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::ERROR)
@@ -31,8 +540,59 @@ fn main() -> Result<(), anyhow::Error> {
         .init();
 
     let cli = Cli::parse();
-    let file = File::open(&cli.path).context("unable to open file input file")?;
+    let format = input_format(&cli)?;
+    let inputs = open_inputs(&cli.paths)?;
+    let mut rejections = rejections(&cli)?;
+    let mut events = events(&cli)?;
+    let mut summary = Summary::default();
+    let accounts = match &cli.state {
+        Some(path) => load_state(path)?,
+        None => Vec::new(),
+    };
+
+    let options = account_options(&cli)?;
+    let output = output(cli.silent);
+    let mut reporting = Reporting::new(rejections.as_mut());
+    if let Some(events) = events.as_mut() {
+        reporting = reporting.with_events(events.as_mut());
+    }
+    if cli.summary {
+        reporting = reporting.with_summary(&mut summary);
+    }
+    if let Some(max_errors) = max_errors(&cli) {
+        reporting = reporting.with_max_errors(max_errors);
+    }
+    let accounts = match format {
+        DataFormat::Csv => CsvProcessor::try_with_options(inputs, output, &csv_options(&cli))?
+            .with_output_format(cli.output_format.into())
+            .with_output_dialect(output_dialect(&cli))
+            .with_ordering(cli.order_by.into())
+            .with_shards(cli.shards)
+            .with_amount_policy(amount_policy(&cli))
+            .with_account_options(options)
+            .with_out_of_order(out_of_order_policy(&cli))
+            .process_with_reporting(accounts, reporting)
+            .context("processing input files failed")?,
+        DataFormat::Jsonl => JsonlProcessor::with_inputs(inputs, output)
+            .with_output_format(cli.output_format.into())
+            .with_output_dialect(output_dialect(&cli))
+            .with_ordering(cli.order_by.into())
+            .with_shards(cli.shards)
+            .with_amount_policy(amount_policy(&cli))
+            .with_account_options(options)
+            .with_out_of_order(out_of_order_policy(&cli))
+            .process_with_reporting(accounts, reporting)
+            .context("processing input files failed")?,
+    };
 
-    let mut processor = CsvProcessor::try_new(file, output(cli.silent))?;
-    processor.process().context("processing input file failed")
+    if cli.summary {
+        print_summary(&summary, cli.summary_format)?;
+    }
+    if let Some(path) = &cli.ledger {
+        write_ledger(path, &accounts)?;
+    }
+    match &cli.state {
+        Some(path) => save_state(path, &accounts),
+        None => Ok(()),
+    }
 }
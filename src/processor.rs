@@ -1,71 +1,489 @@
-use crate::account::Account;
-use crate::account_activity::AccountActivity;
+use crate::account::{Account, AccountOptions, ActivityEffect};
+use crate::account_activity::AccountActivityError::{self, SelfTransfer, Storage};
+use crate::account_activity::{AccountActivity, AccountActivityResult, ActivityKind};
+use crate::chronology::{Admitted, Chronological, OutOfOrderPolicy};
+use crate::engine::{Engine, Outcome};
+use crate::events::{ActivityEvent, EventSink};
+use crate::rejection::{Rejection, RejectionSink, TooManyRejections};
+use crate::sharded::process_activities_sharded;
+use crate::summary::Summary;
+use crate::transfer::Transfer;
+use crate::ClientID;
 use std::collections::HashMap;
 use std::error::Error;
+use std::io;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
 use tracing::debug;
 
+/// A record read from the input, alongside its location within the input.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InputRecord<T> {
+    /// The name of the input the record was read from, if known.
+    pub source: Option<Arc<str>>,
+
+    /// The line of the input the record starts on, if known.
+    pub line: Option<u64>,
+
+    /// The raw content of the record, if known.
+    pub raw: Option<String>,
+
+    pub value: T,
+}
+
+impl<T> From<T> for InputRecord<T> {
+    fn from(value: T) -> Self {
+        Self { source: None, line: None, raw: None, value }
+    }
+}
+
+/// The order in which accounts are written to the output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AccountOrdering {
+    /// Ascending by client ID.
+    #[default]
+    ClientId,
+
+    /// In the order in which the first activity of each client appeared in the input.
+    FirstActivity,
+
+    /// Ascending by total balance. Accounts with equal balances are ordered by client ID.
+    TotalBalance,
+}
+
+impl AccountOrdering {
+    /// Sorts `accounts`, which are expected to be in the order returned by [`process_activities`].
+    pub fn sort(self, accounts: &mut [Account]) {
+        match self {
+            AccountOrdering::ClientId => accounts.sort_by_key(Account::client_id),
+            AccountOrdering::FirstActivity => {}
+            AccountOrdering::TotalBalance => accounts.sort_by(|a, b| {
+                a.total().cmp(&b.total()).then_with(|| a.client_id().cmp(&b.client_id()))
+            }),
+        }
+    }
+}
+
 // TODO: This fn is public to be able to benchmark it. This should better be handled with a
 //       bench-feature instead.
-pub fn process_activities<I, E>(activities: I) -> Vec<Account>
+/// Processes the supplied account activities and returns the resulting accounts in the order in
+/// which the first activity of each client appeared.
+///
+/// Records that could not be parsed and activities that were rejected by an account are skipped
+/// and reported to `rejections`. Transaction IDs must be unique across all clients and dispute
+/// cases must reference transactions of their own client, see
+/// [`TransactionRegistry`](crate::registry::TransactionRegistry).
+///
+/// To apply activities one at a time as they arrive, use an [`Engine`] instead.
+pub fn process_activities<I, R, E>(
+    activities: I,
+    rejections: &mut dyn RejectionSink,
+) -> io::Result<Vec<Account>>
 where
     E: Error,
-    I: Iterator<Item=Result<AccountActivity, E>>,
+    R: Into<InputRecord<Result<AccountActivity, E>>>,
+    I: Iterator<Item=R>,
 {
-    let mut accounts = HashMap::new();
-    for account_activity in activities {
-        match account_activity {
-            Err(err) => {
-                debug!(error = ?err, "error parsing account activity record")
-            }
-            Ok(activity) => {
-                let account = accounts
-                    .entry(activity.client_id())
-                    .or_insert_with(|| Account::new(activity.client_id()));
-                if let Err(err) = account.transaction(activity) {
-                    debug!(
-                            activity = %activity,
-                            transaction.id = %activity.transaction_id(),
-                            client.id = %activity.client_id(),
-                            error = ?err,
-                            "error processing account activity",
-                        );
+    process_activities_from(
+        Vec::new(),
+        AccountOptions::default(),
+        OutOfOrderPolicy::default(),
+        activities,
+        Reporting::new(rejections),
+    )
+}
+
+/// Like [`process_activities`], but applies the activities on top of existing `accounts`, e.g.
+/// restored from a [snapshot](crate::snapshot).
+///
+/// All accounts are [configured](Account::configure) with `options`. Existing accounts precede new
+/// accounts in the returned order. Activities that appear out of chronological order are handled
+/// as configured by `out_of_order`. Rejected records and, if requested, further reports are passed
+/// to `reporting`.
+pub fn process_activities_from<I, R, E>(
+    accounts: Vec<Account>,
+    options: AccountOptions,
+    out_of_order: OutOfOrderPolicy,
+    activities: I,
+    mut reporting: Reporting,
+) -> io::Result<Vec<Account>>
+where
+    E: Error,
+    R: Into<InputRecord<Result<AccountActivity, E>>>,
+    I: Iterator<Item=R>,
+{
+    let mut engine = Engine::from_accounts(accounts, options)?;
+    for admitted in Chronological::new(activities, out_of_order) {
+        let (record, outcome) = match admitted {
+            Admitted::Record(InputRecord { source, line, raw, value }) => match value {
+                Ok(activity) => {
+                    let outcome = engine.apply(activity.clone())?;
+                    (InputRecord { source, line, raw, value: activity }, outcome)
+                }
+                Err(err) => {
+                    reporting.invalid_record(reject_record(source, line, raw, &err))?;
+                    continue;
                 }
+            },
+            Admitted::Rejected(record, err) => (record, Outcome::Rejected(err)),
+        };
+        let InputRecord { source, line, raw, value: activity } = record;
+        match outcome {
+            Outcome::Applied(effects) => {
+                reporting.accepted(&effects, source.as_deref(), line, &activity)?;
+            }
+            Outcome::Rejected(err) => {
+                let rejected = reject_activity(err, source, line, raw, &activity)?;
+                reporting.rejected(activity.kind(), rejected)?;
+            }
+        }
+    }
+    let accounts = engine.drain();
+    reporting.finish(&accounts)?;
+    Ok(accounts)
+}
+
+/// The destinations of the reports about the processed records, besides the resulting accounts.
+///
+/// Rejected records are always reported. Events and the summary are only collected if requested.
+pub struct Reporting<'a> {
+    rejections: &'a mut dyn RejectionSink,
+    pub(crate) events: Option<&'a mut dyn EventSink>,
+    summary: Option<&'a mut Summary>,
+
+    /// The number of rejected records tolerated before processing is aborted, if limited.
+    max_errors: Option<u64>,
+
+    /// The number of records rejected so far.
+    errors: u64,
+}
+
+impl<'a> Reporting<'a> {
+    /// Reports rejected records to `rejections`.
+    pub fn new(rejections: &'a mut dyn RejectionSink) -> Self {
+        Self { rejections, events: None, summary: None, max_errors: None, errors: 0 }
+    }
+
+    /// Reports the outcome of every activity to `events`.
+    pub fn with_events(mut self, events: &'a mut dyn EventSink) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Tallies the records and the resulting accounts in `summary`.
+    pub fn with_summary(mut self, summary: &'a mut Summary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Aborts processing once more than `max_errors` records have been rejected, with a
+    /// [`TooManyRejections`] error naming the first record over the limit. With `0`, processing
+    /// stops at the first rejected record.
+    ///
+    /// The offending record is still reported to the rejections sink before processing is
+    /// aborted, and no later record is read. Records are counted in input order, so sharded
+    /// processing fails with the same record, but its workers may already have applied some of
+    /// the records that follow it.
+    pub fn with_max_errors(mut self, max_errors: u64) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+
+    pub(crate) fn has_events(&self) -> bool {
+        self.events.is_some()
+    }
+
+    pub(crate) fn has_summary(&self) -> bool {
+        self.summary.is_some()
+    }
+
+    pub(crate) fn invalid_record(&mut self, rejection: Rejection) -> io::Result<()> {
+        if let Some(summary) = self.summary.as_deref_mut() {
+            summary.invalid_record();
+        }
+        self.reject(rejection)
+    }
+
+    pub(crate) fn accepted(
+        &mut self,
+        effects: &[ActivityEffect],
+        source: Option<&str>,
+        line: Option<u64>,
+        activity: &AccountActivity,
+    ) -> io::Result<()> {
+        if let (Some(summary), Some(effect)) = (self.summary.as_deref_mut(), effects.first()) {
+            summary.accepted(activity.kind(), effect.status());
+        }
+        if let Some(events) = self.events.as_deref_mut() {
+            for event in accept_activity(effects, source, line, activity) {
+                events.emit(event)?;
             }
         }
+        Ok(())
+    }
+
+    pub(crate) fn rejected(
+        &mut self,
+        kind: ActivityKind,
+        (rejection, event): (Rejection, ActivityEvent),
+    ) -> io::Result<()> {
+        if let Some(summary) = self.summary.as_deref_mut() {
+            summary.rejected(kind, rejection.error_code());
+        }
+        if let Some(events) = self.events.as_deref_mut() {
+            events.emit(event)?;
+        }
+        self.reject(rejection)
+    }
+
+    /// Reports a rejected record and aborts if the limit of rejected records is exceeded.
+    pub(crate) fn reject(&mut self, rejection: Rejection) -> io::Result<()> {
+        self.errors += 1;
+        let exceeded = self.max_errors.filter(|max_errors| self.errors > *max_errors);
+        let Some(max_errors) = exceeded else {
+            return self.rejections.reject(rejection);
+        };
+        self.rejections.reject(rejection.clone())?;
+        self.rejections.flush()?;
+        if let Some(events) = self.events.as_deref_mut() {
+            events.flush()?;
+        }
+        Err(io::Error::other(TooManyRejections { max_errors, rejection }))
+    }
+
+    /// Adds the counts of a summary collected separately, e.g. by another worker.
+    pub(crate) fn merge_summary(&mut self, other: Summary) {
+        if let Some(summary) = self.summary.as_deref_mut() {
+            summary.merge(other);
+        }
     }
-    accounts.into_values().collect()
+
+    /// Flushes all reports and tallies the resulting `accounts`. Called once processing has
+    /// finished.
+    pub(crate) fn finish(self, accounts: &[Account]) -> io::Result<()> {
+        self.rejections.flush()?;
+        if let Some(events) = self.events {
+            events.flush()?;
+        }
+        if let Some(summary) = self.summary {
+            summary.tally_accounts(accounts);
+        }
+        Ok(())
+    }
+}
+
+/// Creates an account configured with `options`.
+pub(crate) fn new_account(client_id: ClientID, options: &AccountOptions) -> Account {
+    Account::with_options(client_id, options.clone())
+}
+
+/// Creates the [`Rejection`] of a record that could not be parsed.
+pub(crate) fn reject_record<E: Error>(
+    source: Option<Arc<str>>,
+    line: Option<u64>,
+    raw: Option<String>,
+    err: &E,
+) -> Rejection {
+    debug!(error = ?err, "error parsing account activity record");
+    Rejection::invalid_record(line, raw, err).with_source(source.as_deref())
+}
+
+/// Returns the clients whose accounts `activity` concerns, i.e. the client of the activity and the
+/// destination of a [transfer](AccountActivity::Transfer), in the order of the legs of the
+/// activity.
+pub(crate) fn concerned_clients(activity: &AccountActivity) -> Vec<ClientID> {
+    match activity {
+        AccountActivity::Transfer(transfer) => vec![transfer.client_id(), transfer.to_client_id()],
+        activity => vec![activity.client_id()],
+    }
+}
+
+/// Applies `activity` to the accounts it concerns, creating missing accounts with `create`.
+///
+/// A [transfer](AccountActivity::Transfer) is applied to both the source and the destination
+/// account, or to neither of them if either account rejects it. A missing destination account is
+/// only kept if the transfer succeeds.
+pub(crate) fn apply_activity<F>(
+    accounts: &mut HashMap<ClientID, Account>,
+    activity: AccountActivity,
+    mut create: F,
+) -> AccountActivityResult<Vec<ActivityEffect>>
+where
+    F: FnMut(ClientID) -> Account,
+{
+    let client_id = activity.client_id();
+    let account = accounts.entry(client_id).or_insert_with(|| create(client_id));
+    let AccountActivity::Transfer(transfer) = activity else {
+        return account.transaction(activity).map(|effect| vec![effect]);
+    };
+    let to_client_id = transfer.to_client_id();
+    if to_client_id == client_id {
+        return Err(SelfTransfer(transfer.id()));
+    }
+    let created = !accounts.contains_key(&to_client_id);
+    accounts.entry(to_client_id).or_insert_with(|| create(to_client_id));
+    // Both accounts have been inserted above
+    let [Some(source), Some(destination)] = accounts.get_disjoint_mut([&client_id, &to_client_id])
+    else {
+        return Ok(Vec::new());
+    };
+    let result = transfer_funds(source, destination, transfer).map(Vec::from);
+    if result.is_err() && created {
+        accounts.remove(&to_client_id);
+    }
+    result
+}
+
+/// Applies both legs of `transfer` to the `source` and `destination` accounts, or neither, and
+/// returns the effects on both accounts.
+pub(crate) fn transfer_funds(
+    source: &mut Account,
+    destination: &mut Account,
+    transfer: Transfer,
+) -> AccountActivityResult<[ActivityEffect; 2]> {
+    destination.check_incoming_transfer(&transfer)?;
+    let outgoing = source.transaction(AccountActivity::Transfer(transfer))?;
+    Ok([outgoing, destination.receive_transfer(transfer)?])
+}
+
+/// Returns the [`Rejection`] and the [`ActivityEvent`] of an activity that failed with `err`.
+///
+/// Failures to access the transaction record of an account are not caused by the activity and
+/// are returned as errors instead, which aborts processing.
+pub(crate) fn reject_activity(
+    err: AccountActivityError,
+    source: Option<Arc<str>>,
+    line: Option<u64>,
+    raw: Option<String>,
+    activity: &AccountActivity,
+) -> io::Result<(Rejection, ActivityEvent)> {
+    if let Storage(failure) = err {
+        return Err(failure.into());
+    }
+    debug!(
+        activity = %activity,
+        transaction.id = %activity.transaction_id(),
+        client.id = %activity.client_id(),
+        error = ?err,
+        "error processing account activity",
+    );
+    let event = ActivityEvent::rejected(line, activity, &err).with_source(source.as_deref());
+    let rejection = Rejection::failed_activity(line, raw, activity, &err);
+    Ok((rejection.with_source(source.as_deref()), event))
+}
+
+/// Returns the [`ActivityEvent`]s of an activity that has been accepted with `effects`.
+pub(crate) fn accept_activity<'a>(
+    effects: &'a [ActivityEffect],
+    source: Option<&'a str>,
+    line: Option<u64>,
+    activity: &'a AccountActivity,
+) -> impl Iterator<Item=ActivityEvent> + 'a {
+    effects
+        .iter()
+        .map(move |effect| ActivityEvent::accepted(line, activity, effect).with_source(source))
 }
 
 /// The processor handles reading account activity records from a source, processing these activities,
 /// and generating account balances as the output.
 pub trait Processor {
-    type Error: Error;
+    type Error: Error + From<io::Error>;
 
     /// Returns an iterator over the parsed records of the input data.
-    fn iter_input(&mut self) -> impl Iterator<Item=Result<AccountActivity, Self::Error>>;
+    fn iter_input(
+        &mut self,
+    ) -> impl Iterator<Item=InputRecord<Result<AccountActivity, Self::Error>>>;
+
+    /// Takes a slice of accounts and serializes it into the output format.
+    fn write(&mut self, accounts: &[Account]) -> Result<(), Self::Error>;
+
+    /// The order in which accounts are passed to [`Processor::write`].
+    fn ordering(&self) -> AccountOrdering {
+        AccountOrdering::default()
+    }
+
+    /// The number of worker threads activities are processed on. See
+    /// [`process_activities_sharded`] for details.
+    fn shards(&self) -> NonZeroUsize {
+        NonZeroUsize::MIN
+    }
 
-    /// Takes a vector of accounts and serializes it into the output format.
-    fn write(&mut self, accounts: Vec<Account>) -> Result<(), Self::Error>;
+    /// The options all accounts are configured with during processing.
+    fn account_options(&self) -> AccountOptions {
+        AccountOptions::default()
+    }
+
+    /// The handling of activities that appear out of chronological order.
+    fn out_of_order(&self) -> OutOfOrderPolicy {
+        OutOfOrderPolicy::default()
+    }
+
+    /// Processes the [`AccountActivity`] data supplied by [`Processor::iter_input`] on top of the
+    /// given `accounts` and generates account balance data that is serialized by
+    /// [`Processor::write`].
+    ///
+    /// Records that are dropped during processing are reported to `rejections`. Returns the
+    /// resulting accounts.
+    fn process(
+        &mut self,
+        accounts: Vec<Account>,
+        rejections: &mut dyn RejectionSink,
+    ) -> Result<Vec<Account>, Self::Error> {
+        self.process_with_reporting(accounts, Reporting::new(rejections))
+    }
 
-    /// Processes the [`AccountActivity`] data supplied by [`Processor::iter_input`] and generates
-    /// account balance data that is serialized by [`Processor::write`].
-    fn process(&mut self) -> Result<(), Self::Error> {
+    /// Like [`Processor::process`], but passes all reports about the processed records to
+    /// `reporting`.
+    fn process_with_reporting(
+        &mut self,
+        accounts: Vec<Account>,
+        reporting: Reporting,
+    ) -> Result<Vec<Account>, Self::Error> {
+        let shards = self.shards();
+        let options = self.account_options();
+        let out_of_order = self.out_of_order();
         let activity_records = self.iter_input();
-        let accounts = process_activities(activity_records);
-        self.write(accounts)
+        let mut accounts = match shards.get() {
+            1 => process_activities_from(
+                accounts,
+                options,
+                out_of_order,
+                activity_records,
+                reporting,
+            )?,
+            _ => process_activities_sharded(
+                accounts,
+                options,
+                out_of_order,
+                activity_records,
+                shards,
+                reporting,
+            )?,
+        };
+        self.ordering().sort(&mut accounts);
+        self.write(&accounts)?;
+        Ok(accounts)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::account::test_utils::LockStatus;
-    use crate::account::Account;
-    use crate::account_activity::AccountActivity;
-    use crate::processor::process_activities;
+    use crate::account::{Account, AccountOptions};
+    use crate::account_activity::{AccountActivity, ActivityKind};
+    use crate::chronology::OutOfOrderPolicy;
     use crate::processor::tests::DummyError::ParseError;
+    use crate::processor::{
+        process_activities, process_activities_from, AccountOrdering, InputRecord, Reporting,
+    };
+    use crate::rejection::{DiscardRejections, Rejection, TooManyRejections};
+    use crate::storage::DiskStorage;
+    use crate::summary::Summary;
     use crate::transaction::TransactionID;
     use crate::ClientID;
     use rust_decimal_macros::dec;
+    use std::env;
+    use std::sync::Arc;
     use thiserror::Error;
 
     #[derive(Error, Debug, Clone)]
@@ -80,7 +498,8 @@ mod tests {
     }
 
     fn test(test_case: TestCase) {
-        let output = process_activities(test_case.activities.into_iter());
+        let output = process_activities(test_case.activities.into_iter(), &mut DiscardRejections)
+            .expect("Expected processing to succeed");
         for (account, expected) in output.into_iter().zip(test_case.expected) {
             assert_eq!(account.client_id(), expected.client_id());
             assert_eq!(account.available(), expected.available());
@@ -155,4 +574,320 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn dropped_records_are_reported() {
+        let deposit = AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0));
+        let withdrawal = AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(15.0));
+        let activities = vec![
+            InputRecord {
+                source: None,
+                line: Some(2),
+                raw: Some("deposit,1,1,10.0".into()),
+                value: Ok(deposit),
+            },
+            InputRecord {
+                source: Some("day-1.csv".into()),
+                line: Some(3),
+                raw: Some("deposit,1".into()),
+                value: Err(ParseError),
+            },
+            InputRecord {
+                source: Some("day-1.csv".into()),
+                line: Some(4),
+                raw: Some("withdrawal,1,2,15.0".into()),
+                value: Ok(withdrawal),
+            },
+        ];
+
+        let mut rejections = Vec::new();
+        process_activities(activities.into_iter(), &mut rejections)
+            .expect("Expected processing to succeed");
+
+        assert_eq!(rejections.len(), 2, "Expected two rejections: {:?}", rejections);
+        assert_eq!(rejections[0].source(), Some("day-1.csv"));
+        assert_eq!(rejections[0].line(), Some(3));
+        assert_eq!(rejections[0].record(), Some("deposit,1"));
+        assert_eq!(rejections[0].error_code(), Rejection::INVALID_RECORD);
+        assert_eq!(rejections[1].line(), Some(4));
+        assert_eq!(rejections[1].record(), Some("withdrawal,1,2,15.0"));
+        assert_eq!(rejections[1].client_id(), Some(ClientID(1)));
+        assert_eq!(rejections[1].transaction_id(), Some(TransactionID(2)));
+        assert_eq!(rejections[1].error_code(), "insufficient_funds");
+    }
+
+    fn ordered_client_ids(ordering: AccountOrdering) -> Vec<ClientID> {
+        let activities: Vec<Result<AccountActivity, DummyError>> = vec![
+            Ok(AccountActivity::deposit(TransactionID(1), ClientID(3), dec!(20.0))),
+            Ok(AccountActivity::deposit(TransactionID(2), ClientID(1), dec!(30.0))),
+            Ok(AccountActivity::deposit(TransactionID(3), ClientID(2), dec!(10.0))),
+            Ok(AccountActivity::deposit(TransactionID(4), ClientID(4), dec!(10.0))),
+        ];
+        let mut accounts = process_activities(activities.into_iter(), &mut DiscardRejections)
+            .expect("Expected processing to succeed");
+        ordering.sort(&mut accounts);
+        accounts.iter().map(Account::client_id).collect()
+    }
+
+    #[test]
+    fn accounts_are_ordered_by_client_id() {
+        assert_eq!(
+            ordered_client_ids(AccountOrdering::ClientId),
+            vec![ClientID(1), ClientID(2), ClientID(3), ClientID(4)],
+        );
+    }
+
+    #[test]
+    fn accounts_are_ordered_by_first_activity() {
+        assert_eq!(
+            ordered_client_ids(AccountOrdering::FirstActivity),
+            vec![ClientID(3), ClientID(1), ClientID(2), ClientID(4)],
+        );
+    }
+
+    #[test]
+    fn accounts_are_ordered_by_total_balance() {
+        assert_eq!(
+            ordered_client_ids(AccountOrdering::TotalBalance),
+            vec![ClientID(2), ClientID(4), ClientID(3), ClientID(1)],
+        );
+    }
+
+    #[test]
+    fn accounts_are_configured_with_options() {
+        let existing = Account::new(ClientID(1));
+        let activities: Vec<Result<AccountActivity, DummyError>> = vec![
+            Ok(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0))),
+            Ok(AccountActivity::deposit(TransactionID(2), ClientID(2), dec!(20.0))),
+        ];
+        let accounts = process_activities_from(
+            vec![existing],
+            AccountOptions::default().with_ledger(true),
+            OutOfOrderPolicy::default(),
+            activities.into_iter(),
+            Reporting::new(&mut DiscardRejections),
+        ).expect("Expected processing to succeed");
+
+        for account in accounts {
+            let entries = account.ledger().map(|ledger| ledger.entries().len());
+            assert_eq!(entries, Some(1), "Expected ledger of client {}", account.client_id());
+        }
+    }
+
+    #[test]
+    fn transaction_records_can_be_held_on_disk() {
+        let existing = || {
+            let mut account = Account::new(ClientID(1));
+            account
+                .transaction(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)))
+                .expect("Test setup: deposit failed");
+            vec![account]
+        };
+        let activities = || vec![
+            Ok::<_, DummyError>(AccountActivity::dispute(TransactionID(1), ClientID(1))),
+            Ok(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(5.0))),
+            Ok(AccountActivity::deposit(TransactionID(2), ClientID(2), dec!(20.0))),
+            Ok(AccountActivity::transfer(TransactionID(3), ClientID(2), ClientID(1), dec!(5.0))),
+            Ok(AccountActivity::chargeback(TransactionID(1), ClientID(1))),
+        ].into_iter();
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+
+        let mut in_memory = Vec::new();
+        let expected = process_activities_from(
+            existing(),
+            AccountOptions::default(),
+            OutOfOrderPolicy::default(),
+            activities(),
+            Reporting::new(&mut in_memory),
+        ).expect("Expected processing in memory to succeed");
+        let mut on_disk = Vec::new();
+        let accounts = process_activities_from(
+            existing(),
+            AccountOptions::default().with_storage(Arc::new(storage)),
+            OutOfOrderPolicy::default(),
+            activities(),
+            Reporting::new(&mut on_disk),
+        ).expect("Expected processing on disk to succeed");
+
+        assert_eq!(accounts, expected, "Expected same accounts regardless of the storage");
+        assert_eq!(on_disk, in_memory, "Expected same rejections regardless of the storage");
+    }
+
+    #[test]
+    fn summary_tallies_records_and_accounts() {
+        let activities: Vec<Result<AccountActivity, DummyError>> = vec![
+            Ok(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0))),
+            Err(ParseError),
+            Ok(AccountActivity::transfer(TransactionID(2), ClientID(1), ClientID(2), dec!(4.0))),
+            Ok(AccountActivity::dispute(TransactionID(9), ClientID(1))),
+            Ok(AccountActivity::withdrawal(TransactionID(3), ClientID(2), dec!(5.0))),
+            Ok(AccountActivity::dispute(TransactionID(1), ClientID(1))),
+            Ok(AccountActivity::chargeback(TransactionID(1), ClientID(1))),
+        ];
+        let mut summary = Summary::default();
+        process_activities_from(
+            Vec::new(),
+            AccountOptions::default(),
+            OutOfOrderPolicy::default(),
+            activities.into_iter(),
+            Reporting::new(&mut DiscardRejections).with_summary(&mut summary),
+        ).expect("Expected processing to succeed");
+
+        assert_eq!(summary.records(), 7);
+        assert_eq!(summary.invalid_records(), 1);
+        assert_eq!(summary.activities(ActivityKind::Transfer).applied, 1,
+                   "Expected transfer to be counted once");
+        assert_eq!(summary.activities(ActivityKind::Dispute).applied, 1);
+        assert_eq!(summary.activities(ActivityKind::Dispute).ignored, 1);
+        assert_eq!(summary.activities(ActivityKind::Withdrawal).rejected, 1);
+        assert_eq!(summary.errors("insufficient_funds"), 1);
+        assert_eq!(summary.accounts(), 2);
+        assert_eq!(summary.locked_accounts(), 1);
+        assert_eq!(summary.total(), dec!(0.0));
+        assert_eq!(summary.held(), dec!(0.0));
+    }
+
+    #[test]
+    fn processing_is_aborted_once_the_error_limit_is_exceeded() {
+        let activities = || vec![
+            Ok::<_, DummyError>(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(5.0))),
+            Err(ParseError),
+            Ok(AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(7.0))),
+            Ok(AccountActivity::deposit(TransactionID(3), ClientID(1), dec!(1.0))),
+        ].into_iter().enumerate().map(|(i, value)| InputRecord {
+            source: Some("day-1.csv".into()),
+            line: Some(i as u64 + 2),
+            raw: None,
+            value,
+        });
+        let process = |max_errors, rejections: &mut Vec<Rejection>| process_activities_from(
+            Vec::new(),
+            AccountOptions::default(),
+            OutOfOrderPolicy::default(),
+            activities(),
+            Reporting::new(rejections).with_max_errors(max_errors),
+        );
+
+        let mut rejections = Vec::new();
+        let err = process(1, &mut rejections).expect_err("Expected processing to be aborted");
+        let err = err
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<TooManyRejections>())
+            .expect("Expected error limit to be exceeded");
+        assert_eq!(err.rejection.line(), Some(4), "Expected second rejection to abort");
+        assert_eq!(err.rejection.error_code(), "insufficient_funds");
+        assert_eq!(rejections.len(), 2, "Expected offending record to be reported");
+
+        let accounts = process(2, &mut Vec::new()).expect("Expected errors to be tolerated");
+        assert_eq!(accounts[0].total(), dec!(6.0));
+    }
+
+    #[test]
+    fn records_after_the_offending_one_are_never_read() {
+        let read = std::cell::Cell::new(0);
+        let activities = vec![
+            Ok::<_, DummyError>(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(5.0))),
+            Ok(AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(7.0))),
+            Ok(AccountActivity::deposit(TransactionID(3), ClientID(1), dec!(1.0))),
+            Ok(AccountActivity::withdrawal(TransactionID(4), ClientID(1), dec!(5.0))),
+        ].into_iter().inspect(|_| read.set(read.get() + 1));
+
+        let mut rejections = Vec::new();
+        process_activities_from(
+            Vec::new(),
+            AccountOptions::default(),
+            OutOfOrderPolicy::default(),
+            activities,
+            Reporting::new(&mut rejections).with_max_errors(0),
+        ).expect_err("Expected processing to be aborted");
+
+        assert_eq!(read.get(), 2, "Expected no record after the offending one to be read");
+        assert_eq!(rejections.len(), 1, "Expected only the offending record to be reported");
+    }
+
+    fn transfer(activities: Vec<AccountActivity>) -> (Vec<(ClientID, String)>, Vec<Rejection>) {
+        let mut rejections = Vec::new();
+        let accounts = process_activities(
+            activities.into_iter().map(Ok::<_, DummyError>),
+            &mut rejections,
+        ).expect("Expected processing to succeed");
+        let balances = accounts
+            .iter()
+            .map(|account| (account.client_id(), account.total().to_string()))
+            .collect();
+        (balances, rejections)
+    }
+
+    #[test]
+    fn transfers_move_funds_between_accounts() {
+        let (balances, rejections) = transfer(vec![
+            AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)),
+            AccountActivity::transfer(TransactionID(2), ClientID(1), ClientID(2), dec!(4.0)),
+        ]);
+
+        assert_eq!(balances, vec![(ClientID(1), "6.0".into()), (ClientID(2), "4.0".into())]);
+        assert!(rejections.is_empty(), "Unexpected rejections: {:?}", rejections);
+    }
+
+    #[test]
+    fn failed_transfers_apply_neither_leg() {
+        let (balances, rejections) = transfer(vec![
+            AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)),
+            AccountActivity::deposit(TransactionID(2), ClientID(2), dec!(10.0)),
+            // Exceeds the available funds of the source account
+            AccountActivity::transfer(TransactionID(3), ClientID(1), ClientID(2), dec!(15.0)),
+            // Reuses a transaction ID already recorded by the destination account
+            AccountActivity::transfer(TransactionID(2), ClientID(1), ClientID(2), dec!(5.0)),
+            AccountActivity::transfer(TransactionID(4), ClientID(1), ClientID(1), dec!(5.0)),
+        ]);
+
+        assert_eq!(balances, vec![(ClientID(1), "10.0".into()), (ClientID(2), "10.0".into())]);
+        let codes = rejections.iter().map(Rejection::error_code).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["insufficient_funds", "duplicate_transaction", "self_transfer"]);
+    }
+
+    #[test]
+    fn failed_transfers_cannot_be_disputed() {
+        let (balances, rejections) = transfer(vec![
+            AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)),
+            AccountActivity::transfer(TransactionID(2), ClientID(1), ClientID(2), dec!(1000.0)),
+            AccountActivity::dispute(TransactionID(2), ClientID(1)),
+            AccountActivity::chargeback(TransactionID(2), ClientID(1)),
+        ]);
+
+        assert_eq!(balances, vec![(ClientID(1), "10.0".into())],
+                   "Expected failed transfer to neither move funds nor create the destination");
+        let codes = rejections.iter().map(Rejection::error_code).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["insufficient_funds", "transaction_failed", "transaction_failed"]);
+    }
+
+    #[test]
+    fn transaction_ids_are_unique_across_clients() {
+        let (balances, rejections) = transfer(vec![
+            AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)),
+            AccountActivity::deposit(TransactionID(1), ClientID(2), dec!(10.0)),
+            AccountActivity::dispute(TransactionID(1), ClientID(3)),
+            AccountActivity::deposit(TransactionID(2), ClientID(2), dec!(5.0)),
+        ]);
+
+        assert_eq!(balances, vec![(ClientID(1), "10.0".into()), (ClientID(2), "5.0".into())]);
+        let codes = rejections.iter().map(Rejection::error_code).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["duplicate_transaction", "foreign_transaction"]);
+    }
+
+    #[test]
+    fn transfers_to_locked_accounts_are_rejected() {
+        let (balances, rejections) = transfer(vec![
+            AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(10.0)),
+            AccountActivity::deposit(TransactionID(2), ClientID(2), dec!(10.0)),
+            AccountActivity::dispute(TransactionID(2), ClientID(2)),
+            AccountActivity::chargeback(TransactionID(2), ClientID(2)),
+            AccountActivity::transfer(TransactionID(3), ClientID(1), ClientID(2), dec!(5.0)),
+        ]);
+
+        assert_eq!(balances, vec![(ClientID(1), "10.0".into()), (ClientID(2), "0.0".into())]);
+        let codes = rejections.iter().map(Rejection::error_code).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["account_locked"]);
+    }
 }
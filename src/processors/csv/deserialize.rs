@@ -5,8 +5,10 @@
 //! For more details, see [this issue](https://github.com/BurntSushi/rust-csv/issues/211).
 //!
 use crate::account_activity::AccountActivity;
+use crate::admin::AdminAction;
 use crate::dispute::DisputeCase;
 use crate::transaction::Transaction;
+use crate::transfer::Transfer;
 use serde::de::{Error, MapAccess};
 use serde::{de, Deserialize};
 
@@ -32,19 +34,24 @@ impl<'de> de::Visitor<'de> for RecordVisitor {
     where
         A: MapAccess<'de>,
     {
-        let _key = map.next_key::<&'de str>()?;
+        let _key = map.next_key::<de::IgnoredAny>()?;
         let kind = map.next_value::<&'de str>()?;
 
         let variant = de::value::MapAccessDeserializer::new(map);
         match kind {
             "deposit" => Transaction::deserialize(variant).map(AccountActivity::Deposit),
             "withdrawal" => Transaction::deserialize(variant).map(AccountActivity::Withdrawal),
+            "transfer" => Transfer::deserialize(variant).map(AccountActivity::Transfer),
             "dispute" => DisputeCase::deserialize(variant).map(AccountActivity::Dispute),
             "resolve" => DisputeCase::deserialize(variant).map(AccountActivity::Resolve),
             "chargeback" => DisputeCase::deserialize(variant).map(AccountActivity::Chargeback),
-            kind => Err(A::Error::unknown_variant(
-                kind, &["deposit", "withdrawal", "dispute", "resolve", "chargeback"],
-            )),
+            "freeze" => AdminAction::deserialize(variant).map(AccountActivity::Freeze),
+            "unfreeze" => AdminAction::deserialize(variant).map(AccountActivity::Unfreeze),
+            "unlock" => AdminAction::deserialize(variant).map(AccountActivity::Unlock),
+            kind => Err(A::Error::unknown_variant(kind, &[
+                "deposit", "withdrawal", "transfer", "dispute", "resolve", "chargeback", "freeze",
+                "unfreeze", "unlock",
+            ])),
         }
     }
 }
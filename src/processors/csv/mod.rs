@@ -1,9 +1,12 @@
 mod processor;
 mod deserialize;
-mod writer;
+mod options;
+pub mod writer;
 pub mod reader;
 
+pub use options::{ColumnMapping, CsvDialect, CsvOptions, Terminator};
 pub use processor::CsvProcessor;
+use crate::amount::AmountError;
 use std::io;
 use thiserror::Error;
 
@@ -14,9 +17,12 @@ pub enum CsvProcessorError {
     #[error("error processing csv: {0}")]
     Csv(#[from] csv::Error),
 
-    #[error("error processing csv: {0}")]
+    #[error("error processing csv")]
     Io(#[from] io::Error),
 
     #[error("invalid format: {0}")]
     InvalidFormat(String),
+
+    #[error("invalid amount: {0}")]
+    InvalidAmount(#[from] AmountError),
 }
@@ -0,0 +1,255 @@
+use csv::{ReaderBuilder, Trim, WriterBuilder};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The character CSV records are terminated with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    /// Reads records terminated with `\r`, `\n` or `\r\n` and terminates written records with
+    /// `\n`.
+    #[default]
+    Newline,
+
+    /// Like [`Terminator::Newline`], but terminates written records with `\r\n`.
+    Crlf,
+
+    /// Reads and writes records terminated with the given character.
+    Byte(u8),
+}
+
+/// The format of CSV records: the characters that delimit fields, quote fields and terminate
+/// records, and whether there is a header line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvDialect {
+    delimiter: u8,
+    quote: u8,
+    terminator: Terminator,
+    comment: Option<u8>,
+    has_headers: bool,
+    trim: bool,
+}
+
+impl Default for CsvDialect {
+    /// Comma separated fields quoted with `"`, a header line and whitespace trimmed around fields.
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            terminator: Terminator::default(),
+            comment: None,
+            has_headers: true,
+            trim: true,
+        }
+    }
+}
+
+impl CsvDialect {
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn with_terminator(mut self, terminator: Terminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Sets the character that starts comment lines, which are skipped when reading. Written
+    /// fields that start with it are quoted.
+    pub fn with_comment(mut self, comment: Option<u8>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Sets whether inputs start with a header line and whether a header line is written.
+    ///
+    /// Inputs without a header line are read as the [required columns](
+    /// crate::processors::csv::reader::CsvReader::REQUIRED_COLUMNS) in that order.
+    pub fn with_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Sets whether whitespace around fields is trimmed when reading.
+    pub fn with_trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    pub fn has_headers(&self) -> bool {
+        self.has_headers
+    }
+
+    /// Returns a builder for readers of this dialect.
+    pub(crate) fn reader(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .terminator(self.terminator.reading())
+            .comment(self.comment)
+            .has_headers(self.has_headers)
+            .trim(if self.trim { Trim::All } else { Trim::None });
+        builder
+    }
+
+    /// Returns the record within the bytes read for it, without its terminator and without the
+    /// empty lines and comment lines the reader skipped before it.
+    pub(crate) fn raw_record<'a>(&self, mut bytes: &'a [u8]) -> &'a [u8] {
+        while let Some(&first) = bytes.first() {
+            let skipped = if self.terminator.is_terminator(first) {
+                1
+            } else if Some(first) == self.comment {
+                bytes
+                    .iter()
+                    .position(|&byte| self.terminator.is_terminator(byte))
+                    .map_or(bytes.len(), |end| end + 1)
+            } else {
+                break;
+            };
+            bytes = &bytes[skipped..];
+        }
+        self.terminator.strip(bytes)
+    }
+
+    /// Returns a builder for writers of this dialect.
+    pub(crate) fn writer(&self) -> WriterBuilder {
+        let mut builder = WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .terminator(self.terminator.writing())
+            .comment(self.comment)
+            .has_headers(self.has_headers);
+        builder
+    }
+}
+
+impl Terminator {
+    fn reading(self) -> csv::Terminator {
+        match self {
+            Terminator::Newline | Terminator::Crlf => csv::Terminator::CRLF,
+            Terminator::Byte(byte) => csv::Terminator::Any(byte),
+        }
+    }
+
+    fn writing(self) -> csv::Terminator {
+        match self {
+            Terminator::Newline => csv::Terminator::Any(b'\n'),
+            Terminator::Crlf => csv::Terminator::CRLF,
+            Terminator::Byte(byte) => csv::Terminator::Any(byte),
+        }
+    }
+
+    fn is_terminator(self, byte: u8) -> bool {
+        match self {
+            Terminator::Newline | Terminator::Crlf => byte == b'\r' || byte == b'\n',
+            Terminator::Byte(terminator) => byte == terminator,
+        }
+    }
+
+    /// Removes the terminator from the end of a record.
+    fn strip(self, record: &[u8]) -> &[u8] {
+        let stripped = match self {
+            Terminator::Newline | Terminator::Crlf => record
+                .strip_suffix(b"\r\n")
+                .or_else(|| record.strip_suffix(b"\n"))
+                .or_else(|| record.strip_suffix(b"\r")),
+            Terminator::Byte(byte) => record.strip_suffix(&[byte]),
+        };
+        stripped.unwrap_or(record)
+    }
+}
+
+/// Maps the column names used by an input to the column names the reader expects, e.g. `kind` to
+/// `type` or `client_id` to `client`. Columns without an alias are read by their own name.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    aliases: HashMap<String, String>,
+}
+
+impl ColumnMapping {
+    /// Reads the column named `alias` as `column`.
+    pub fn with_alias(mut self, alias: impl Into<String>, column: impl Into<String>) -> Self {
+        self.aliases.insert(alias.into(), column.into());
+        self
+    }
+
+    /// Returns the name the column with the given header is read as.
+    pub fn column<'a>(&'a self, header: &'a str) -> &'a str {
+        self.aliases.get(header).map_or(header, String::as_str)
+    }
+}
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    /// Parses aliases of the form `alias=column`, separated by commas.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|alias| !alias.trim().is_empty())
+            .try_fold(Self::default(), |mapping, alias| {
+                let (alias, column) = alias
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected alias=column, got {}", alias))?;
+                Ok(mapping.with_alias(alias.trim(), column.trim()))
+            })
+    }
+}
+
+/// The options CSV inputs are read with.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    dialect: CsvDialect,
+    columns: ColumnMapping,
+}
+
+impl CsvOptions {
+    /// Sets the format of the records of the inputs.
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Sets the aliases of the columns of the inputs.
+    pub fn with_columns(mut self, columns: ColumnMapping) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn dialect(&self) -> &CsvDialect {
+        &self.dialect
+    }
+
+    pub fn columns(&self) -> &ColumnMapping {
+        &self.columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColumnMapping;
+
+    #[test]
+    fn aliases_are_parsed() {
+        let mapping = "kind=type, client_id = client".parse::<ColumnMapping>()
+            .expect("Expected aliases to be parsed");
+        assert_eq!(mapping, ColumnMapping::default()
+            .with_alias("kind", "type")
+            .with_alias("client_id", "client"));
+        assert_eq!(mapping.column("kind"), "type");
+        assert_eq!(mapping.column("tx"), "tx");
+
+        let result = "kind:type".parse::<ColumnMapping>();
+        assert_eq!(result, Err("expected alias=column, got kind:type".into()));
+    }
+}
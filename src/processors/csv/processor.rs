@@ -1,18 +1,27 @@
-use crate::account::Account;
+use crate::account::{Account, AccountOptions};
 use crate::account_activity::AccountActivity;
-use crate::processor::Processor;
+use crate::amount::AmountPolicy;
+use crate::chronology::OutOfOrderPolicy;
+use crate::processor::{AccountOrdering, InputRecord, Processor};
 use crate::processors::csv::reader::CsvReader;
-use crate::processors::csv::writer::CsvWriter;
-use crate::processors::csv::CsvProcessorError;
+use crate::processors::csv::{CsvDialect, CsvOptions, CsvProcessorError};
+use crate::processors::{apply_amount_policy, AccountWriter, OutputFormat};
+use anyhow::Context;
 use std::io::{Read, Write};
+use std::num::NonZeroUsize;
 
 pub struct CsvProcessor<R, W>
 where
     R: Read,
     W: Write,
 {
-    reader: CsvReader<R>,
-    writer: CsvWriter<W>,
+    readers: Vec<CsvReader<R>>,
+    writer: AccountWriter<W>,
+    ordering: AccountOrdering,
+    amount_policy: Option<AmountPolicy>,
+    shards: NonZeroUsize,
+    account_options: AccountOptions,
+    out_of_order: OutOfOrderPolicy,
 }
 
 impl<R, W> CsvProcessor<R, W>
@@ -20,13 +29,96 @@ where
     R: Read,
     W: Write,
 {
+    /// Creates a processor that reads CSV and writes accounts as CSV.
     pub fn try_new(input: R, output: W) -> Result<Self, anyhow::Error> {
         let reader = CsvReader::try_new(input)?;
-        let writer = CsvWriter::new(output);
-        Ok(Self {
-            reader,
-            writer,
-        })
+        Ok(Self::with_readers(vec![reader], output))
+    }
+
+    /// Like [`CsvProcessor::try_new`], but reads several named inputs one after another.
+    ///
+    /// The header line of every input is validated up front. Records and errors carry the name of
+    /// the input they originate from.
+    pub fn try_with_inputs<I>(inputs: I, output: W) -> Result<Self, anyhow::Error>
+    where
+        I: IntoIterator<Item=(String, R)>,
+    {
+        Self::try_with_options(inputs, output, &CsvOptions::default())
+    }
+
+    /// Like [`CsvProcessor::try_with_inputs`], but reads the inputs with the given `options`.
+    pub fn try_with_options<I>(
+        inputs: I,
+        output: W,
+        options: &CsvOptions,
+    ) -> Result<Self, anyhow::Error>
+    where
+        I: IntoIterator<Item=(String, R)>,
+    {
+        let readers = inputs
+            .into_iter()
+            .map(|(name, input)| {
+                CsvReader::try_with_options(input, options)
+                    .map(|reader| reader.with_source(name.as_str()))
+                    .with_context(|| format!("invalid input {}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::with_readers(readers, output))
+    }
+
+    fn with_readers(readers: Vec<CsvReader<R>>, output: W) -> Self {
+        Self {
+            readers,
+            writer: AccountWriter::new(output, OutputFormat::Csv),
+            ordering: AccountOrdering::default(),
+            amount_policy: None,
+            shards: NonZeroUsize::MIN,
+            account_options: AccountOptions::default(),
+            out_of_order: OutOfOrderPolicy::default(),
+        }
+    }
+
+    /// Sets the format accounts are written in.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.writer = self.writer.with_format(format);
+        self
+    }
+
+    /// Sets the dialect accounts are written in if written as CSV.
+    pub fn with_output_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.writer = self.writer.with_csv_dialect(dialect);
+        self
+    }
+
+    /// Sets the number of worker threads activities are processed on.
+    pub fn with_shards(mut self, shards: NonZeroUsize) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    /// Sets the policy that input amounts and output balances are subject to.
+    pub fn with_amount_policy(mut self, policy: AmountPolicy) -> Self {
+        self.writer = self.writer.with_amount_policy(policy);
+        self.amount_policy = Some(policy);
+        self
+    }
+
+    /// Sets the options all accounts are configured with.
+    pub fn with_account_options(mut self, options: AccountOptions) -> Self {
+        self.account_options = options;
+        self
+    }
+
+    /// Sets the handling of activities that appear out of chronological order.
+    pub fn with_out_of_order(mut self, policy: OutOfOrderPolicy) -> Self {
+        self.out_of_order = policy;
+        self
+    }
+
+    /// Sets the order in which accounts are written to the output.
+    pub fn with_ordering(mut self, ordering: AccountOrdering) -> Self {
+        self.ordering = ordering;
+        self
     }
 }
 
@@ -37,11 +129,99 @@ where
 {
     type Error = CsvProcessorError;
 
-    fn iter_input(&mut self) -> impl Iterator<Item=Result<AccountActivity, Self::Error>> {
-        self.reader.iter()
+    fn iter_input(
+        &mut self,
+    ) -> impl Iterator<Item=InputRecord<Result<AccountActivity, Self::Error>>> {
+        let amount_policy = self.amount_policy;
+        self.readers
+            .iter_mut()
+            .flat_map(CsvReader::records)
+            .map(move |record| apply_amount_policy(record, amount_policy))
     }
 
-    fn write(&mut self, accounts: Vec<Account>) -> Result<(), Self::Error> {
-        self.writer.serialize(accounts.iter())
+    fn write(&mut self, accounts: &[Account]) -> Result<(), Self::Error> {
+        Ok(self.writer.write(accounts)?)
+    }
+
+    fn ordering(&self) -> AccountOrdering {
+        self.ordering
+    }
+
+    fn shards(&self) -> NonZeroUsize {
+        self.shards
+    }
+
+    fn account_options(&self) -> AccountOptions {
+        self.account_options.clone()
+    }
+
+    fn out_of_order(&self) -> OutOfOrderPolicy {
+        self.out_of_order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CsvProcessor;
+    use crate::processor::{Processor, Reporting};
+    use crate::rejection::Rejection;
+
+    #[test]
+    fn inputs_are_processed_in_order() {
+        let inputs = [
+            ("day-1.csv", "type, client, tx, amount\ndeposit, 1, 1, 10.0\ndeposit, 2, 2, 5.0"),
+            ("day-2.csv", "type, client, tx, amount\nwithdrawal, 1, 3, 4.0\nwithdrawal, 2, 4, 6.0"),
+        ];
+        let mut output = Vec::new();
+        let mut rejections: Vec<Rejection> = Vec::new();
+        CsvProcessor::try_with_inputs(
+            inputs.map(|(name, input)| (name.to_string(), input.as_bytes())),
+            &mut output,
+        )
+            .expect("Expected inputs to be valid")
+            .process(Vec::new(), &mut rejections)
+            .expect("Expected processing to succeed");
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        assert_eq!(output.trim(), [
+            "client,available,held,total,locked,lock_reason",
+            "1,6.0,0.0,6.0,false,",
+            "2,5.0,0.0,5.0,false,",
+        ].join("\n"));
+        assert_eq!(rejections.len(), 1, "Expected one rejection: {:?}", rejections);
+        assert_eq!(rejections[0].source(), Some("day-2.csv"));
+        assert_eq!(rejections[0].line(), Some(3));
+    }
+
+    #[test]
+    fn invalid_header_names_the_input() {
+        let inputs = [
+            ("day-1.csv", "type, client, tx, amount\ndeposit, 1, 1, 10.0"),
+            ("day-2.csv", "deposit, 1, 2, 10.0"),
+        ];
+        let result = CsvProcessor::try_with_inputs(
+            inputs.map(|(name, input)| (name.to_string(), input.as_bytes())),
+            Vec::new(),
+        );
+
+        let message = result.err().map(|err| format!("{:#}", err));
+        assert_eq!(message.as_deref(), Some(
+            "invalid input day-2.csv: invalid format: header line lacks required columns: \
+            type, client, tx, amount"
+        ));
+    }
+
+    #[test]
+    fn aborted_processing_names_the_reason_once() {
+        let input = "type, client, tx, amount\nwithdrawal, 1, 1, 1.0";
+        let mut rejections: Vec<Rejection> = Vec::new();
+        let err = CsvProcessor::try_new(input.as_bytes(), Vec::new())
+            .expect("Expected input to be valid")
+            .process_with_reporting(Vec::new(), Reporting::new(&mut rejections).with_max_errors(0))
+            .expect_err("Expected processing to be aborted");
+
+        let message = format!("{:#}", anyhow::Error::from(err));
+        assert_eq!(message.matches("insufficient funds").count(), 1,
+                   "Expected reason to be named once: {}", message);
     }
 }
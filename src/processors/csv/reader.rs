@@ -1,14 +1,82 @@
+use crate::processor::InputRecord;
 use crate::processors::csv::CsvProcessorError::InvalidFormat;
-use crate::processors::csv::{CsvProcessorError, CsvProcessorResult};
-use csv::{Reader, StringRecord, Trim};
+use crate::processors::csv::{CsvDialect, CsvOptions, CsvProcessorError, CsvProcessorResult};
+use csv::{Position, Reader, StringRecord};
 use serde::de::DeserializeOwned;
 use std::io;
+use std::iter;
 use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A record read from a CSV input, alongside its position in the input, its fields and its raw
+/// content.
+#[derive(Debug)]
+pub struct CsvRecord<D> {
+    /// The position the record starts at, or the position reading failed at if the record could
+    /// not be read.
+    pub position: Option<Position>,
+
+    /// The fields of the record, or `None` if the record could not be read.
+    pub record: Option<StringRecord>,
+
+    /// The record as it appears in the input, including quotes and whitespace but without its
+    /// terminator. Invalid UTF-8 is replaced.
+    pub raw: Option<String>,
+
+    pub value: CsvProcessorResult<D>,
+}
+
+impl<D> CsvRecord<D> {
+    /// The line of the input the record starts on, if known.
+    pub fn line(&self) -> Option<u64> {
+        self.position.as_ref().map(Position::line)
+    }
+}
+
+/// Keeps the bytes read from the input until they are taken, so the raw content of every record
+/// can be recovered after it has been parsed.
+pub struct Recorder<R> {
+    inner: R,
+
+    /// The bytes read from the input that have not been taken yet.
+    buffer: Vec<u8>,
+
+    /// The offset of the first byte of `buffer` within the input.
+    offset: u64,
+}
+
+impl<R> Recorder<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, buffer: Vec::new(), offset: 0 }
+    }
+
+    /// Takes the bytes of the input from offset `start` up to `end`, discarding all bytes before.
+    fn take(&mut self, start: u64, end: u64) -> Vec<u8> {
+        let len = self.buffer.len();
+        let index = |offset: u64| {
+            usize::try_from(offset.saturating_sub(self.offset)).map_or(len, |index| index.min(len))
+        };
+        let (start, end) = (index(start), index(end));
+        let bytes = self.buffer[start..end.max(start)].to_vec();
+        self.buffer.drain(..end);
+        self.offset += end as u64;
+        bytes
+    }
+}
+
+impl<R: io::Read> io::Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.buffer.extend_from_slice(&buf[..read]);
+        Ok(read)
+    }
+}
 
 pub struct AccountActivityIter<'r, R: 'r, D> {
-    reader: &'r mut Reader<R>,
-    record: StringRecord,
+    reader: &'r mut Reader<Recorder<R>>,
     headers: StringRecord,
+    order: Option<Vec<usize>>,
+    dialect: CsvDialect,
     phantom_data: PhantomData<D>,
 }
 
@@ -16,70 +84,183 @@ impl<'r, R: io::Read, D: DeserializeOwned> AccountActivityIter<'r, R, D> {
     fn new(reader: &'r mut CsvReader<R>) -> AccountActivityIter<'r, R, D> {
         Self {
             reader: &mut reader.reader,
-            record: StringRecord::new(),
             headers: reader.headers.clone(),
+            order: reader.order.clone(),
+            dialect: reader.dialect,
             phantom_data: PhantomData,
         }
     }
+
+    /// Deserializes a record that may lack trailing fields, which are treated as absent.
+    fn deserialize(&self, record: &StringRecord) -> csv::Result<D> {
+        let Some(order) = &self.order else {
+            if self.headers.len() > record.len() {
+                let mut headers = self.headers.clone();
+                headers.truncate(record.len());
+                return record.deserialize(Some(&headers));
+            }
+            return record.deserialize(Some(&self.headers));
+        };
+        // Fields beyond the end of a short record are absent, regardless of the column order
+        let present = order.iter().copied().filter(|&index| index < record.len());
+        let mut fields = present.clone().map(|index| &record[index]).collect::<StringRecord>();
+        fields.set_position(record.position().cloned());
+        let headers = present.map(|index| &self.headers[index]).collect::<StringRecord>();
+        fields.deserialize(Some(&headers))
+    }
+
+    /// Takes the raw content of the record that starts at `start` and has just been read.
+    fn raw(&mut self, start: Option<&Position>) -> Option<String> {
+        let end = self.reader.position().byte();
+        let bytes = self.reader.get_mut().take(start?.byte(), end);
+        Some(String::from_utf8_lossy(self.dialect.raw_record(&bytes)).into_owned())
+    }
 }
 
 impl<'r, R: io::Read, D: DeserializeOwned> Iterator for AccountActivityIter<'r, R, D>
 {
-    type Item = CsvProcessorResult<D>;
+    type Item = CsvRecord<D>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.reader.read_record(&mut self.record) {
-            Err(err) => Some(Err(err.into())),
+        let mut record = StringRecord::new();
+        match self.reader.read_record(&mut record) {
+            Err(err) => Some(CsvRecord {
+                position: err.position().cloned(),
+                record: None,
+                raw: self.raw(err.position()),
+                value: Err(err.into()),
+            }),
             Ok(false) => None,
-            Ok(true) => {
-                let deserialized_record = if self.headers.len() > self.record.len() {
-                    let mut headers = self.headers.clone();
-                    headers.truncate(self.record.len());
-                    self.record.deserialize(Some(&headers))
-                } else {
-                    self.record.deserialize(Some(&self.headers))
-                };
-                Some(deserialized_record.map_err(CsvProcessorError::Csv))
-            }
+            Ok(true) => Some(CsvRecord {
+                position: record.position().cloned(),
+                raw: self.raw(record.position()),
+                value: self.deserialize(&record).map_err(CsvProcessorError::Csv),
+                record: Some(record),
+            }),
         }
     }
 }
 
+/// An iterator over deserialized records that also yields the location and the raw content of
+/// every record.
+pub struct InputRecordIter<'r, R: 'r, D> {
+    inner: AccountActivityIter<'r, R, D>,
+    source: Option<Arc<str>>,
+}
+
+impl<'r, R: io::Read, D: DeserializeOwned> Iterator for InputRecordIter<'r, R, D> {
+    type Item = InputRecord<CsvProcessorResult<D>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.inner.next()?;
+        let line = record.line();
+        Some(InputRecord { source: self.source.clone(), line, raw: record.raw, value: record.value })
+    }
+}
+
 pub struct CsvReader<R> {
-    pub reader: Reader<R>,
+    pub reader: Reader<Recorder<R>>,
+
+    /// The names the columns of the input are read as, after applying the column mapping.
     pub headers: StringRecord,
+
+    /// The indices of the columns in the order they are deserialized in, or `None` if the input
+    /// starts with the `type` column.
+    order: Option<Vec<usize>>,
+
+    dialect: CsvDialect,
+
+    source: Option<Arc<str>>,
 }
 
 impl<R> CsvReader<R>
 where
     R: io::Read,
 {
+    /// The columns every input must provide.
+    pub const REQUIRED_COLUMNS: [&'static str; 4] = ["type", "client", "tx", "amount"];
+
+    /// Creates a reader and validates the header line of the input.
+    ///
+    /// Fails with [`InvalidFormat`] if the header line cannot be read or lacks any of the
+    /// [required columns](Self::REQUIRED_COLUMNS). An empty input is valid.
     pub fn try_new(reader: R) -> CsvProcessorResult<Self> {
-        let mut csv_reader = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .flexible(true)
-            .trim(Trim::All)
-            .from_reader(reader);
-        let headers = csv_reader
-            .headers()
-            .map_err(|_| InvalidFormat("invalid format: missing header line".into()))?
-            .clone();
-        Ok(Self { reader: csv_reader, headers })
-    }
-
-    pub fn iter<T>(&mut self) -> AccountActivityIter<R, T>
+        Self::try_with_options(reader, &CsvOptions::default())
+    }
+
+    /// Like [`CsvReader::try_new`], but reads the input with the given `options`.
+    ///
+    /// Columns are identified by their names after applying the
+    /// [column mapping](CsvOptions::columns), so they may appear in any order. Inputs without a
+    /// header line are read as the required columns in the given order.
+    pub fn try_with_options(reader: R, options: &CsvOptions) -> CsvProcessorResult<Self> {
+        let mut csv_reader = options.dialect().reader().flexible(true).from_reader(Recorder::new(reader));
+        let headers = if options.dialect().has_headers() {
+            csv_reader
+                .headers()
+                .map_err(|_| InvalidFormat("missing header line".into()))?
+                .iter()
+                .map(|header| options.columns().column(header))
+                .collect::<StringRecord>()
+        } else {
+            StringRecord::from(Self::REQUIRED_COLUMNS.to_vec())
+        };
+        let missing = Self::REQUIRED_COLUMNS
+            .into_iter()
+            .filter(|column| !headers.is_empty() && !headers.iter().any(|header| header == *column))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(InvalidFormat(format!(
+                "header line lacks required columns: {}",
+                missing.join(", "),
+            )));
+        }
+        // The type of the activity must be deserialized first, followed by the other columns
+        let order = headers
+            .iter()
+            .position(|header| header == "type")
+            .filter(|&kind| kind > 0)
+            .map(|kind| {
+                let others = (0..headers.len()).filter(|&index| index != kind);
+                iter::once(kind).chain(others).collect()
+            });
+        let dialect = *options.dialect();
+        Ok(Self { reader: csv_reader, headers, order, dialect, source: None })
+    }
+
+    /// Sets the name of the input that is attached to every record.
+    pub fn with_source(mut self, source: impl Into<Arc<str>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Returns an iterator over the records of the input, each alongside its position and raw
+    /// fields.
+    pub fn iter<T>(&mut self) -> AccountActivityIter<'_, R, T>
     where
         T: DeserializeOwned,
     {
         AccountActivityIter::new(self)
     }
+
+    /// Like [`CsvReader::iter`], but yields every record alongside its location in the input.
+    pub fn records<T>(&mut self) -> InputRecordIter<'_, R, T>
+    where
+        T: DeserializeOwned,
+    {
+        let source = self.source.clone();
+        InputRecordIter { inner: AccountActivityIter::new(self), source }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::CsvReader;
     use crate::account_activity::AccountActivity;
-    use crate::processors::csv::CsvProcessorResult;
+    use crate::admin::AdminAction;
+    use crate::processors::csv::CsvProcessorError::InvalidFormat;
+    use crate::processors::csv::{ColumnMapping, CsvDialect, CsvOptions};
+    use crate::timestamp::Timestamp;
     use crate::transaction::TransactionID;
     use crate::ClientID;
     use rust_decimal_macros::dec;
@@ -95,18 +276,133 @@ mod tests {
             let mut reader = CsvReader::try_new(input.as_bytes()).unwrap();
             reader
                 .iter()
-                .map(|r| r.unwrap())
+                .map(|r| r.value.unwrap())
                 .collect::<Vec<AccountActivity>>()
         };
         assert_eq!(transactions, test_case.expected);
     }
 
+    #[test]
+    fn records_carry_their_location() {
+        let input = [
+            "type,       client, tx, amount",
+            "deposit,    1,      1,  100.0",
+            "withdrawal, 1,      2",
+        ].join("\n");
+        let mut reader = CsvReader::try_new(input.as_bytes()).unwrap();
+        let records = reader.records::<AccountActivity>().collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].line, Some(2));
+        assert_eq!(records[0].raw.as_deref(), Some("deposit,    1,      1,  100.0"));
+        assert_eq!(
+            records[0].value.as_ref().ok(),
+            Some(&AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(100.0))),
+        );
+        assert_eq!(records[1].line, Some(3));
+        assert_eq!(records[1].raw.as_deref(), Some("withdrawal, 1,      2"));
+        assert!(records[1].value.is_err(), "Expected withdrawal without amount to fail");
+    }
+
+    #[test]
+    fn records_carry_their_position() {
+        let input = b"type, client, tx, amount\n\
+            deposit, 1, 1, 100.0\n\
+            bogus, 1, 2, 1.0\n\
+            deposit, \xff";
+        let mut reader = CsvReader::try_new(&input[..]).unwrap();
+        let records = reader.iter::<AccountActivity>().collect::<Vec<_>>();
+
+        let positions = records
+            .iter()
+            .map(|record| {
+                let position = record.position.as_ref().expect("Expected record to be located");
+                (position.line(), position.byte(), position.record())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(2, 25, 1), (3, 46, 2), (4, 63, 3)]);
+        assert!(records[1].value.is_err(), "Expected unknown activity to fail");
+        assert_eq!(records[1].record.as_ref().map(|record| record.len()), Some(4),
+                   "Expected fields of failed record to be kept");
+        assert!(records[2].value.is_err(), "Expected invalid UTF-8 to fail");
+        assert!(records[2].record.is_none(), "Expected unreadable record to have no fields");
+        assert_eq!(records[2].raw.as_deref(), Some("deposit, \u{fffd}"),
+                   "Expected raw content of unreadable record to be kept");
+    }
+
+    #[test]
+    fn columns_are_mapped_and_may_appear_in_any_order() {
+        let input = [
+            "client_id, tx, kind,    amount",
+            "1,         1,  deposit, 100.0",
+            "1,         1,  dispute",
+            "1,         1,  resolve,",
+        ].join("\n");
+        let columns = ColumnMapping::default()
+            .with_alias("kind", "type")
+            .with_alias("client_id", "client");
+        let options = CsvOptions::default().with_columns(columns);
+        let mut reader = CsvReader::try_with_options(input.as_bytes(), &options).unwrap();
+        let activities = reader
+            .iter::<AccountActivity>()
+            .map(|r| r.value.unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(activities, vec![
+            AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(100.0)),
+            AccountActivity::dispute(TransactionID(1), ClientID(1)),
+            AccountActivity::resolve(TransactionID(1), ClientID(1)),
+        ]);
+    }
+
+    #[test]
+    fn records_are_read_in_the_configured_dialect() {
+        let input = [
+            "# exported by partner",
+            "deposit;1;1;'1,5'",
+            "dispute;1;1",
+        ].join("\n");
+        let dialect = CsvDialect::default()
+            .with_delimiter(b';')
+            .with_quote(b'\'')
+            .with_comment(Some(b'#'))
+            .with_headers(false);
+        let options = CsvOptions::default().with_dialect(dialect);
+        let mut reader = CsvReader::try_with_options(input.as_bytes(), &options).unwrap();
+        let records = reader.records::<AccountActivity>().collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].raw.as_deref(), Some("deposit;1;1;'1,5'"));
+        assert!(records[0].value.is_err(), "Expected amount with decimal comma to fail");
+        assert_eq!(records[1].value.as_ref().ok(),
+                   Some(&AccountActivity::dispute(TransactionID(1), ClientID(1))));
+    }
+
     #[test]
     fn missing_headers_cause_error() {
         let input = "deposit, 1, 1, 100.0".to_string();
-        let mut reader = CsvReader::try_new(input.as_bytes()).unwrap();
-        let result = reader.iter().collect::<Vec<CsvProcessorResult<AccountActivity>>>();
-        assert!(result.is_empty());
+        let result = CsvReader::try_new(input.as_bytes());
+        assert!(matches!(result, Err(InvalidFormat(_))), "Expected missing header line to fail");
+    }
+
+    #[test]
+    fn missing_columns_cause_error() {
+        let input = "type, client, amount\ndeposit, 1, 100.0".to_string();
+        let result = CsvReader::try_new(input.as_bytes());
+        let message = result.err().map(|err| err.to_string());
+        assert_eq!(message.as_deref(),
+                   Some("invalid format: header line lacks required columns: tx"));
+    }
+
+    #[test]
+    fn records_carry_their_source() {
+        let input = "type, client, tx, amount\ndeposit, 1, 1, 100.0".to_string();
+        let mut reader = CsvReader::try_new(input.as_bytes()).unwrap().with_source("day-1.csv");
+        let sources = reader
+            .records::<AccountActivity>()
+            .map(|record| record.source)
+            .collect::<Vec<_>>();
+        assert_eq!(sources, vec![Some("day-1.csv".into())]);
     }
 
     #[test]
@@ -167,6 +463,86 @@ mod tests {
         })
     }
 
+    #[test]
+    fn transfers_are_serialized() {
+        test(TestCase {
+            input: vec![
+                "type,     client, tx, amount, to_client, timestamp",
+                "deposit,  1,      1,  8.0,    ,",
+                "transfer, 1,      2,  1.5,    2,",
+                "transfer, 1,      3,  2.5,    2,         1700000000",
+            ],
+            expected: vec![
+                AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(8.0)),
+                AccountActivity::transfer(TransactionID(2), ClientID(1), ClientID(2), dec!(1.5)),
+                AccountActivity::transfer(TransactionID(3), ClientID(1), ClientID(2), dec!(2.5))
+                    .with_timestamp(Timestamp(1_700_000_000)),
+            ],
+        })
+    }
+
+    #[test]
+    fn timestamps_are_optional() {
+        test(TestCase {
+            input: vec![
+                "type,     client, tx, amount, timestamp",
+                "deposit,  1,      1,  8.0,    1700000000",
+                "deposit,  1,      2,  1.5,",
+                "dispute,  1,      1,  ,       1700000100",
+                "resolve,  1,      1",
+            ],
+            expected: vec![
+                AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(8.0))
+                    .with_timestamp(Timestamp(1_700_000_000)),
+                AccountActivity::deposit(TransactionID(2), ClientID(1), dec!(1.5)),
+                AccountActivity::dispute(TransactionID(1), ClientID(1))
+                    .with_timestamp(Timestamp(1_700_000_100)),
+                AccountActivity::resolve(TransactionID(1), ClientID(1)),
+            ],
+        })
+    }
+
+    #[test]
+    fn admin_actions_are_serialized() {
+        test(TestCase {
+            input: vec![
+                "type,     client, tx, amount, reason,          operator",
+                "freeze,   1,      1,  ,       suspected fraud, alice",
+                "unfreeze, 1,      2,  ,       review passed,",
+                "unlock,   2,      3,  ,       refund settled,  bob",
+            ],
+            expected: vec![
+                AccountActivity::Freeze(
+                    AdminAction::new(TransactionID(1), ClientID(1), "suspected fraud")
+                        .with_operator("alice"),
+                ),
+                AccountActivity::unfreeze(TransactionID(2), ClientID(1), "review passed"),
+                AccountActivity::Unlock(
+                    AdminAction::new(TransactionID(3), ClientID(2), "refund settled")
+                        .with_operator("bob"),
+                ),
+            ],
+        })
+    }
+
+    #[test]
+    fn amounts_keep_their_precision() {
+        let input = [
+            "type,    client, tx, amount",
+            "deposit, 1,      1,  10.12345678",
+            "deposit, 1,      2,  10.000",
+        ].join("\n");
+        let mut reader = CsvReader::try_new(input.as_bytes()).unwrap();
+        let amounts = reader
+            .iter::<AccountActivity>()
+            .map(|r| match r.value.unwrap() {
+                AccountActivity::Deposit(transaction) => Some(transaction.amount().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(amounts, vec![Some("10.12345678".into()), Some("10.000".into())]);
+    }
+
     #[test]
     fn disputes_are_serialized() {
         test(TestCase {
@@ -1,4 +1,6 @@
-use crate::processors::csv::CsvProcessorResult;
+use crate::account::Account;
+use crate::amount::AmountPolicy;
+use crate::processors::csv::{CsvDialect, CsvProcessorResult};
 use serde::Serialize;
 use std::io;
 
@@ -7,6 +9,7 @@ where
     W: io::Write,
 {
     writer: csv::Writer<W>,
+    amount_policy: Option<AmountPolicy>,
 }
 
 impl<W> CsvWriter<W>
@@ -14,7 +17,30 @@ where
     W: io::Write,
 {
     pub fn new(writer: W) -> Self {
-        Self { writer: csv::Writer::from_writer(writer) }
+        Self::with_dialect(writer, &CsvDialect::default())
+    }
+
+    /// Creates a writer that writes records in the given `dialect`.
+    pub fn with_dialect(writer: W, dialect: &CsvDialect) -> Self {
+        Self { writer: dialect.writer().from_writer(writer), amount_policy: None }
+    }
+
+    /// Sets the policy that the balances of accounts are normalized with.
+    pub fn with_amount_policy(mut self, policy: AmountPolicy) -> Self {
+        self.amount_policy = Some(policy);
+        self
+    }
+
+    /// Serializes the balances of accounts, normalizing them if an [`AmountPolicy`] has been set.
+    pub fn serialize_accounts<'a, I>(&mut self, accounts: I) -> CsvProcessorResult<()>
+    where
+        I: Iterator<Item=&'a Account>,
+    {
+        let amount_policy = self.amount_policy;
+        self.serialize(accounts.map(|account| match &amount_policy {
+            None => account.balances(),
+            Some(policy) => account.balances().normalized(policy),
+        }))
     }
 
     pub fn serialize<S, I>(&mut self, records: I) -> CsvProcessorResult<()>
@@ -33,6 +59,7 @@ where
 mod tests {
     use super::*;
     use crate::account::{test_utils::LockStatus, Account};
+    use crate::processors::csv::Terminator;
     use crate::ClientID;
     use rust_decimal_macros::dec;
 
@@ -46,18 +73,68 @@ mod tests {
             LockStatus::Locked,
         );
         let expected = [
-            "client,available,held,total,locked",
-            "101,10.0,20.0,30.0,true",
+            "client,available,held,total,locked,lock_reason",
+            "101,10.0,20.0,30.0,true,chargeback of transaction 0",
         ].join("\n");
 
         let mut output = Vec::new();
         let result = {
             let mut writer = CsvWriter::new(&mut output);
-            writer.serialize([account].iter())
+            writer.serialize_accounts([account].iter())
         };
         let output = String::from_utf8(output).expect("Failed to convert output into string");
 
         assert!(result.is_ok(), "Expected serialization of account to succeed: {:?}", result);
         assert_eq!(output.trim(), expected.trim());
     }
+
+    #[test]
+    fn serialize_account_with_amount_policy() {
+        let account = Account::with_values(
+            ClientID(101),
+            dec!(10),
+            dec!(0.12345),
+            dec!(10.12345),
+            LockStatus::Unlocked,
+        );
+        let expected = [
+            "client,available,held,total,locked,lock_reason",
+            "101,10.0000,0.1234,10.1234,false,",
+        ].join("\n");
+
+        let mut output = Vec::new();
+        let result = {
+            let mut writer = CsvWriter::new(&mut output).with_amount_policy(AmountPolicy::new(4));
+            writer.serialize_accounts([account].iter())
+        };
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        assert!(result.is_ok(), "Expected serialization of account to succeed: {:?}", result);
+        assert_eq!(output.trim(), expected.trim());
+    }
+
+    #[test]
+    fn serialize_account_in_dialect() {
+        let account = Account::with_values(
+            ClientID(101),
+            dec!(10.0),
+            dec!(20.0),
+            dec!(30.0),
+            LockStatus::Locked,
+        );
+        let dialect = CsvDialect::default()
+            .with_delimiter(b'\t')
+            .with_terminator(Terminator::Crlf)
+            .with_headers(false);
+
+        let mut output = Vec::new();
+        let result = {
+            let mut writer = CsvWriter::with_dialect(&mut output, &dialect);
+            writer.serialize_accounts([account].iter())
+        };
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        assert!(result.is_ok(), "Expected serialization of account to succeed: {:?}", result);
+        assert_eq!(output, "101\t10.0\t20.0\t30.0\ttrue\tchargeback of transaction 0\r\n");
+    }
 }
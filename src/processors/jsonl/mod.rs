@@ -0,0 +1,22 @@
+mod processor;
+pub mod reader;
+pub mod writer;
+
+pub use processor::JsonlProcessor;
+use crate::amount::AmountError;
+use std::io;
+use thiserror::Error;
+
+pub type JsonlProcessorResult<T> = Result<T, JsonlProcessorError>;
+
+#[derive(Error, Debug)]
+pub enum JsonlProcessorError {
+    #[error("error processing json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("error processing json")]
+    Io(#[from] io::Error),
+
+    #[error("invalid amount: {0}")]
+    InvalidAmount(#[from] AmountError),
+}
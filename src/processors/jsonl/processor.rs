@@ -0,0 +1,173 @@
+use crate::account::{Account, AccountOptions};
+use crate::account_activity::AccountActivity;
+use crate::amount::AmountPolicy;
+use crate::chronology::OutOfOrderPolicy;
+use crate::processor::{AccountOrdering, InputRecord, Processor};
+use crate::processors::csv::CsvDialect;
+use crate::processors::jsonl::reader::JsonlReader;
+use crate::processors::jsonl::JsonlProcessorError;
+use crate::processors::{apply_amount_policy, AccountWriter, OutputFormat};
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
+
+pub struct JsonlProcessor<R, W>
+where
+    R: Read,
+    W: Write,
+{
+    readers: Vec<JsonlReader<R>>,
+    writer: AccountWriter<W>,
+    ordering: AccountOrdering,
+    amount_policy: Option<AmountPolicy>,
+    shards: NonZeroUsize,
+    account_options: AccountOptions,
+    out_of_order: OutOfOrderPolicy,
+}
+
+impl<R, W> JsonlProcessor<R, W>
+where
+    R: Read,
+    W: Write,
+{
+    /// Creates a processor that reads JSON Lines and writes accounts as JSON Lines.
+    pub fn new(input: R, output: W) -> Self {
+        Self::with_readers(vec![JsonlReader::new(input)], output)
+    }
+
+    /// Like [`JsonlProcessor::new`], but reads several named inputs one after another.
+    ///
+    /// Records and errors carry the name of the input they originate from.
+    pub fn with_inputs<I>(inputs: I, output: W) -> Self
+    where
+        I: IntoIterator<Item=(String, R)>,
+    {
+        let readers = inputs
+            .into_iter()
+            .map(|(name, input)| JsonlReader::new(input).with_source(name))
+            .collect();
+        Self::with_readers(readers, output)
+    }
+
+    fn with_readers(readers: Vec<JsonlReader<R>>, output: W) -> Self {
+        Self {
+            readers,
+            writer: AccountWriter::new(output, OutputFormat::Jsonl),
+            ordering: AccountOrdering::default(),
+            amount_policy: None,
+            shards: NonZeroUsize::MIN,
+            account_options: AccountOptions::default(),
+            out_of_order: OutOfOrderPolicy::default(),
+        }
+    }
+
+    /// Sets the format accounts are written in.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.writer = self.writer.with_format(format);
+        self
+    }
+
+    /// Sets the dialect accounts are written in if written as CSV.
+    pub fn with_output_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.writer = self.writer.with_csv_dialect(dialect);
+        self
+    }
+
+    /// Sets the number of worker threads activities are processed on.
+    pub fn with_shards(mut self, shards: NonZeroUsize) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    /// Sets the policy that input amounts and output balances are subject to.
+    pub fn with_amount_policy(mut self, policy: AmountPolicy) -> Self {
+        self.writer = self.writer.with_amount_policy(policy);
+        self.amount_policy = Some(policy);
+        self
+    }
+
+    /// Sets the options all accounts are configured with.
+    pub fn with_account_options(mut self, options: AccountOptions) -> Self {
+        self.account_options = options;
+        self
+    }
+
+    /// Sets the handling of activities that appear out of chronological order.
+    pub fn with_out_of_order(mut self, policy: OutOfOrderPolicy) -> Self {
+        self.out_of_order = policy;
+        self
+    }
+
+    /// Sets the order in which accounts are written to the output.
+    pub fn with_ordering(mut self, ordering: AccountOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+}
+
+impl<R, W> Processor for JsonlProcessor<R, W>
+where
+    R: Read,
+    W: Write,
+{
+    type Error = JsonlProcessorError;
+
+    fn iter_input(
+        &mut self,
+    ) -> impl Iterator<Item=InputRecord<Result<AccountActivity, Self::Error>>> {
+        let amount_policy = self.amount_policy;
+        self.readers
+            .iter_mut()
+            .flat_map(JsonlReader::records)
+            .map(move |record| apply_amount_policy(record, amount_policy))
+    }
+
+    fn write(&mut self, accounts: &[Account]) -> Result<(), Self::Error> {
+        Ok(self.writer.write(accounts)?)
+    }
+
+    fn ordering(&self) -> AccountOrdering {
+        self.ordering
+    }
+
+    fn shards(&self) -> NonZeroUsize {
+        self.shards
+    }
+
+    fn account_options(&self) -> AccountOptions {
+        self.account_options.clone()
+    }
+
+    fn out_of_order(&self) -> OutOfOrderPolicy {
+        self.out_of_order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonlProcessor;
+    use crate::processor::Processor;
+    use crate::processors::OutputFormat;
+    use crate::rejection::DiscardRejections;
+
+    #[test]
+    fn activities_are_processed() {
+        let input = [
+            r#"{"type":"deposit","client":2,"tx":1,"amount":"10.0"}"#,
+            r#"{"type":"deposit","client":1,"tx":2,"amount":5}"#,
+            r#"{"type":"withdrawal","client":2,"tx":3,"amount":2.5}"#,
+            r#"{"type":"dispute","client":1,"tx":2}"#,
+        ].join("\n");
+        let mut output = Vec::new();
+        JsonlProcessor::new(input.as_bytes(), &mut output)
+            .with_output_format(OutputFormat::Csv)
+            .process(Vec::new(), &mut DiscardRejections)
+            .expect("Expected processing to succeed");
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        assert_eq!(output.trim(), [
+            "client,available,held,total,locked,lock_reason",
+            "1,0,5,5,false,",
+            "2,7.5,0.0,7.5,false,",
+        ].join("\n"));
+    }
+}
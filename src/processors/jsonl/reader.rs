@@ -0,0 +1,189 @@
+use crate::account_activity::AccountActivity;
+use crate::processor::InputRecord;
+use crate::processors::jsonl::{JsonlProcessorError, JsonlProcessorResult};
+use serde::de::value::MapDeserializer;
+use serde::{de, Deserialize};
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::sync::Arc;
+use std::{io, iter};
+
+/// Deserializes an [`AccountActivity`] from a JSON object with the same [`Deserialize`] impl that
+/// reads CSV records, which expects the `type` field first. Amounts may be given as JSON numbers
+/// and are passed on as strings, so they are parsed from their textual representation and keep
+/// their precision.
+fn deserialize_activity(line: &str) -> serde_json::Result<AccountActivity> {
+    let fields = serde_json::from_str::<BTreeMap<&str, &RawValue>>(line)?;
+    let kind = *fields.get("type").ok_or_else(|| de::Error::missing_field("type"))?;
+    let amount = fields
+        .get("amount")
+        .filter(|amount| amount.get().starts_with(|c: char| c == '-' || c.is_ascii_digit()))
+        .map(|amount| RawValue::from_string(format!("\"{}\"", amount.get())))
+        .transpose()?;
+    let fields = iter::once(("type", kind))
+        .chain(fields.iter()
+            .filter(|(&name, _)| name != "type" && (name != "amount" || amount.is_none()))
+            .map(|(&name, &value)| (name, value)))
+        .chain(amount.as_deref().map(|amount| ("amount", amount)));
+    AccountActivity::deserialize(MapDeserializer::new(fields))
+}
+
+/// An iterator over the account activities of a [`JsonlReader`] that also yields the location
+/// and the raw content of every record.
+pub struct InputRecordIter<'r, R: 'r> {
+    reader: &'r mut JsonlReader<R>,
+}
+
+impl<'r, R: io::Read> Iterator for InputRecordIter<'r, R> {
+    type Item = InputRecord<JsonlProcessorResult<AccountActivity>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.next_record()
+    }
+}
+
+/// Reads account activities from JSON objects, one per line. Blank lines are skipped.
+pub struct JsonlReader<R> {
+    reader: io::BufReader<R>,
+    buffer: String,
+    line: u64,
+    failed: bool,
+    source: Option<Arc<str>>,
+}
+
+impl<R> JsonlReader<R>
+where
+    R: io::Read,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: io::BufReader::new(reader),
+            buffer: String::new(),
+            line: 0,
+            failed: false,
+            source: None,
+        }
+    }
+
+    /// Sets the name of the input that is attached to every record.
+    pub fn with_source(mut self, source: impl Into<Arc<str>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Returns an iterator that yields every record alongside its location in the input.
+    pub fn records(&mut self) -> InputRecordIter<'_, R> {
+        InputRecordIter { reader: self }
+    }
+
+    fn next_record(&mut self) -> Option<InputRecord<JsonlProcessorResult<AccountActivity>>> {
+        // Reading cannot continue reliably after an I/O error
+        if self.failed {
+            return None;
+        }
+        loop {
+            self.buffer.clear();
+            self.line += 1;
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => {
+                    self.failed = true;
+                    return Some(InputRecord {
+                        source: self.source.clone(),
+                        line: Some(self.line),
+                        raw: None,
+                        value: Err(JsonlProcessorError::Io(err)),
+                    });
+                }
+            }
+            let raw = self.buffer.trim();
+            if raw.is_empty() {
+                continue;
+            }
+            let value = deserialize_activity(raw).map_err(JsonlProcessorError::Json);
+            return Some(InputRecord {
+                source: self.source.clone(),
+                line: Some(self.line),
+                raw: Some(raw.to_string()),
+                value,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonlReader;
+    use crate::account_activity::AccountActivity;
+    use crate::admin::AdminAction;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn activities_are_deserialized() {
+        let input = [
+            r#"{"type":"deposit","client":1,"tx":1,"amount":100.0}"#,
+            r#"{"client":1,"tx":2,"amount":"1.5","type":"withdrawal"}"#,
+            r#"{"type":"dispute","client":1,"tx":1}"#,
+            r#"{"type":"resolve","client":1,"tx":1,"amount":null}"#,
+            r#"{"type":"chargeback","client":1,"tx":1}"#,
+            r#"{"type":"transfer","client":1,"tx":3,"to_client":2,"amount":2.5}"#,
+            r#"{"type":"freeze","client":1,"tx":4,"reason":"fraud","operator":"ops"}"#,
+        ].join("\n");
+        let mut reader = JsonlReader::new(input.as_bytes());
+        let activities = reader
+            .records()
+            .map(|record| record.value.ok())
+            .collect::<Vec<_>>();
+
+        assert_eq!(activities, vec![
+            Some(AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(100.0))),
+            Some(AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(1.5))),
+            Some(AccountActivity::dispute(TransactionID(1), ClientID(1))),
+            Some(AccountActivity::resolve(TransactionID(1), ClientID(1))),
+            Some(AccountActivity::chargeback(TransactionID(1), ClientID(1))),
+            Some(AccountActivity::transfer(TransactionID(3), ClientID(1), ClientID(2), dec!(2.5))),
+            Some(AccountActivity::Freeze(
+                AdminAction::new(TransactionID(4), ClientID(1), "fraud").with_operator("ops"),
+            )),
+        ]);
+    }
+
+    #[test]
+    fn amounts_keep_their_precision() {
+        let input = [
+            r#"{"type":"deposit","client":1,"tx":1,"amount":10.12345678901234567}"#,
+            r#"{"type":"deposit","client":1,"tx":2,"amount":"10.000"}"#,
+        ].join("\n");
+        let mut reader = JsonlReader::new(input.as_bytes());
+        let amounts = reader
+            .records()
+            .map(|record| match record.value {
+                Ok(AccountActivity::Deposit(transaction)) => Some(transaction.amount().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(amounts, vec![Some("10.12345678901234567".into()), Some("10.000".into())]);
+    }
+
+    #[test]
+    fn records_carry_their_location() {
+        let input = [
+            r#"{"type":"deposit","client":1,"tx":1,"amount":"1.0"}"#,
+            "",
+            r#"{"type":"withdrawal","client":1,"tx":2}"#,
+        ].join("\n");
+        let mut reader = JsonlReader::new(input.as_bytes());
+        let records = reader.records().collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2, "Expected blank line to be skipped");
+        assert_eq!(records[0].line, Some(1));
+        assert_eq!(records[1].line, Some(3));
+        assert_eq!(records[1].raw.as_deref(), Some(r#"{"type":"withdrawal","client":1,"tx":2}"#));
+        assert!(records[1].value.is_err(), "Expected withdrawal without amount to fail");
+    }
+}
@@ -0,0 +1,93 @@
+use crate::account::Account;
+use crate::amount::AmountPolicy;
+use crate::processors::jsonl::JsonlProcessorResult;
+use serde::Serialize;
+use std::io;
+use std::io::Write;
+
+pub struct JsonlWriter<W>
+where
+    W: io::Write,
+{
+    writer: io::BufWriter<W>,
+    amount_policy: Option<AmountPolicy>,
+}
+
+impl<W> JsonlWriter<W>
+where
+    W: io::Write,
+{
+    pub fn new(writer: W) -> Self {
+        Self { writer: io::BufWriter::new(writer), amount_policy: None }
+    }
+
+    /// Sets the policy that the balances of accounts are normalized with.
+    pub fn with_amount_policy(mut self, policy: AmountPolicy) -> Self {
+        self.amount_policy = Some(policy);
+        self
+    }
+
+    /// Serializes the balances of accounts, normalizing them if an [`AmountPolicy`] has been set.
+    pub fn serialize_accounts<'a, I>(&mut self, accounts: I) -> JsonlProcessorResult<()>
+    where
+        I: Iterator<Item=&'a Account>,
+    {
+        let amount_policy = self.amount_policy;
+        self.serialize(accounts.map(|account| match &amount_policy {
+            None => account.balances(),
+            Some(policy) => account.balances().normalized(policy),
+        }))
+    }
+
+    /// Serializes every record as a JSON object on a line of its own.
+    pub fn serialize<S, I>(&mut self, records: I) -> JsonlProcessorResult<()>
+    where
+        S: Serialize,
+        I: Iterator<Item=S>,
+    {
+        for record in records {
+            serde_json::to_writer(&mut self.writer, &record)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(self.writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{test_utils::LockStatus, Account};
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn serialize_accounts() {
+        let accounts = [
+            Account::with_values(
+                ClientID(101),
+                dec!(10.0),
+                dec!(20.0),
+                dec!(30.0),
+                LockStatus::Locked,
+            ),
+            Account::with_values(ClientID(2), dec!(1), dec!(0), dec!(1), LockStatus::Unlocked),
+        ];
+        let expected = [
+            concat!(
+                r#"{"client":101,"available":"10.0","held":"20.0","total":"30.0","locked":true,"#,
+                r#""lock_reason":"chargeback of transaction 0"}"#,
+            ),
+            concat!(
+                r#"{"client":2,"available":"1","held":"0","total":"1","locked":false,"#,
+                r#""lock_reason":null}"#,
+            ),
+        ].join("\n");
+
+        let mut output = Vec::new();
+        let result = JsonlWriter::new(&mut output).serialize_accounts(accounts.iter());
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        assert!(result.is_ok(), "Expected serialization of accounts to succeed: {:?}", result);
+        assert_eq!(output.trim(), expected);
+    }
+}
@@ -1 +1,26 @@
+use crate::account_activity::AccountActivity;
+use crate::amount::{AmountError, AmountPolicy};
+use crate::processor::InputRecord;
+
 pub mod csv;
+pub mod jsonl;
+mod output;
+
+pub use output::{AccountWriter, OutputFormat};
+
+/// Applies `policy` to the amount of a successfully parsed activity, if a policy is set.
+///
+/// This happens after deserialization, as the amounts' `Deserialize` impl cannot be configured
+/// with a policy, see [`AmountPolicy`].
+pub(crate) fn apply_amount_policy<E>(
+    mut record: InputRecord<Result<AccountActivity, E>>,
+    policy: Option<AmountPolicy>,
+) -> InputRecord<Result<AccountActivity, E>>
+where
+    E: From<AmountError>,
+{
+    if let Some(policy) = policy {
+        record.value = record.value.and_then(|activity| Ok(policy.apply_to(activity)?));
+    }
+    record
+}
@@ -0,0 +1,114 @@
+use crate::account::Account;
+use crate::amount::AmountPolicy;
+use crate::processors::csv::writer::CsvWriter;
+use crate::processors::csv::CsvDialect;
+use crate::processors::jsonl::writer::JsonlWriter;
+use std::io;
+
+/// The format accounts are written in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Comma separated values, including a header line.
+    #[default]
+    Csv,
+
+    /// JSON objects, one per line.
+    Jsonl,
+}
+
+/// Writes the balances of accounts in the configured [`OutputFormat`].
+pub struct AccountWriter<W>
+where
+    W: io::Write,
+{
+    writer: W,
+    format: OutputFormat,
+    csv_dialect: CsvDialect,
+    amount_policy: Option<AmountPolicy>,
+}
+
+impl<W> AccountWriter<W>
+where
+    W: io::Write,
+{
+    pub fn new(writer: W, format: OutputFormat) -> Self {
+        Self { writer, format, csv_dialect: CsvDialect::default(), amount_policy: None }
+    }
+
+    /// Sets the format accounts are written in.
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the dialect accounts are written in if written as CSV.
+    pub fn with_csv_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.csv_dialect = dialect;
+        self
+    }
+
+    /// Sets the policy that the balances of accounts are normalized with.
+    pub fn with_amount_policy(mut self, policy: AmountPolicy) -> Self {
+        self.amount_policy = Some(policy);
+        self
+    }
+
+    /// Writes the balances of the given accounts.
+    pub fn write(&mut self, accounts: &[Account]) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Csv => {
+                let mut writer = CsvWriter::with_dialect(&mut self.writer, &self.csv_dialect);
+                if let Some(policy) = self.amount_policy {
+                    writer = writer.with_amount_policy(policy);
+                }
+                writer.serialize_accounts(accounts.iter()).map_err(io::Error::other)
+            }
+            OutputFormat::Jsonl => {
+                let mut writer = JsonlWriter::new(&mut self.writer);
+                if let Some(policy) = self.amount_policy {
+                    writer = writer.with_amount_policy(policy);
+                }
+                writer.serialize_accounts(accounts.iter()).map_err(io::Error::other)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::test_utils::LockStatus;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+
+    fn write(format: OutputFormat) -> String {
+        let account = Account::with_values(
+            ClientID(1),
+            dec!(1.5),
+            dec!(0),
+            dec!(1.5),
+            LockStatus::Unlocked,
+        );
+        let mut output = Vec::new();
+        AccountWriter::new(&mut output, format)
+            .with_amount_policy(AmountPolicy::new(2))
+            .write(&[account])
+            .expect("Expected accounts to be written");
+        String::from_utf8(output).expect("Failed to convert output into string")
+    }
+
+    #[test]
+    fn accounts_are_written_in_the_configured_format() {
+        assert_eq!(write(OutputFormat::Csv).trim(), [
+            "client,available,held,total,locked,lock_reason",
+            "1,1.50,0.00,1.50,false,",
+        ].join("\n"));
+        assert_eq!(
+            write(OutputFormat::Jsonl).trim(),
+            concat!(
+                r#"{"client":1,"available":"1.50","held":"0.00","total":"1.50","locked":false,"#,
+                r#""lock_reason":null}"#,
+            ),
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Enforcement of the global uniqueness of transaction IDs.
+//!
+//! Every [`Account`] only knows its own transactions, so it cannot detect a transaction that
+//! reuses the ID of another client's transaction. The [`TransactionRegistry`] keeps track of the
+//! clients every transaction ID belongs to across all accounts. It is consulted before an activity
+//! is handed to an account and learns about the transactions the accounts have recorded
+//! afterwards. The owners are held by an [`OwnerStore`] of the [`Storage`] of the accounts.
+use crate::account::{Account, AccountOptions};
+use crate::account_activity::AccountActivity;
+use crate::account_activity::AccountActivityError::{DuplicateTransaction, ForeignTransaction};
+use crate::account_activity::AccountActivityResult;
+use crate::storage::{MemoryOwnerStore, OwnerStore, Storage, StorageResult};
+use crate::transaction::TransactionID;
+use crate::ClientID;
+
+/// The clients a transaction belongs to.
+///
+/// A transaction belongs to the client that issued it and, for an accepted transfer, to the
+/// receiving client as well.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Owners {
+    client_id: ClientID,
+    counterparty: Option<ClientID>,
+}
+
+impl Owners {
+    pub fn new(client_id: ClientID, counterparty: Option<ClientID>) -> Self {
+        Self { client_id, counterparty }
+    }
+
+    /// Returns the client that issued the transaction.
+    pub fn client_id(&self) -> ClientID {
+        self.client_id
+    }
+
+    /// Returns the receiving client of a transfer.
+    pub fn counterparty(&self) -> Option<ClientID> {
+        self.counterparty
+    }
+
+    /// Returns the owners of the transaction issued by `activity` if it is accepted, or `None` if
+    /// the activity does not issue a transaction.
+    pub fn of(activity: &AccountActivity) -> Option<Self> {
+        let counterparty = match activity {
+            AccountActivity::Deposit(_) | AccountActivity::Withdrawal(_) => None,
+            AccountActivity::Transfer(transfer) => Some(transfer.to_client_id()),
+            _ => return None,
+        };
+        Some(Self { client_id: activity.client_id(), counterparty })
+    }
+
+    /// Returns the owners that have recorded the transaction issued by `activity` once it has been
+    /// applied, or `None` if no account has recorded it.
+    ///
+    /// An accepted transaction has been recorded by all accounts it concerns. A rejected
+    /// transaction may still have been recorded as
+    /// [failed](crate::account::TransactionState::Failed) by `account`, the account of the
+    /// issuing client, if it exists.
+    pub fn recorded(
+        activity: &AccountActivity,
+        accepted: bool,
+        account: Option<&Account>,
+    ) -> StorageResult<Option<Self>> {
+        let Some(owners) = Self::of(activity) else {
+            return Ok(None);
+        };
+        if accepted {
+            return Ok(Some(owners));
+        }
+        let recorded = match account {
+            Some(account) => account.transaction_state(activity.transaction_id())?.is_some(),
+            None => false,
+        };
+        Ok(recorded.then_some(Self { counterparty: None, ..owners }))
+    }
+
+    fn contains(&self, client_id: ClientID) -> bool {
+        self.client_id == client_id || self.counterparty == Some(client_id)
+    }
+}
+
+/// The owners of all transaction IDs seen so far.
+///
+/// The owners are held in memory by default, which grows with the number of transactions. A
+/// registry [opened](TransactionRegistry::open) from a [`Storage`] holds them in the storage.
+#[derive(Debug)]
+pub struct TransactionRegistry {
+    owners: Box<dyn OwnerStore>,
+}
+
+impl Default for TransactionRegistry {
+    fn default() -> Self {
+        Self { owners: Box::new(MemoryOwnerStore::default()) }
+    }
+}
+
+impl TransactionRegistry {
+    /// Creates an empty registry that holds the owners in a new store of `storage`.
+    pub fn open(storage: &dyn Storage) -> Self {
+        Self { owners: storage.open_owners() }
+    }
+
+    /// Creates an empty registry that holds the owners in the storage configured by `options`, or
+    /// in memory if the accounts hold their transaction record in memory.
+    pub fn with_options(options: &AccountOptions) -> Self {
+        options.storage().map_or_else(Self::default, Self::open)
+    }
+
+    /// Registers the transactions recorded by the given accounts, e.g. restored from a
+    /// [snapshot](crate::snapshot).
+    pub fn with_accounts<'a, I>(mut self, accounts: I) -> StorageResult<Self>
+    where
+        I: IntoIterator<Item=&'a Account>,
+    {
+        for account in accounts {
+            let client_id = account.client_id();
+            account.for_each_transaction_id(|transaction_id| {
+                // Both accounts involved in a transfer record its ID
+                let owners = match self.owners.get(transaction_id)? {
+                    Some(owners) => Owners { counterparty: Some(client_id), ..owners },
+                    None => Owners { client_id, counterparty: None },
+                };
+                self.owners.insert(transaction_id, owners)
+            })?;
+        }
+        Ok(self)
+    }
+
+    /// Checks whether `activity` is consistent with the transactions registered so far.
+    ///
+    /// A transaction is rejected if its ID is registered for another client. A dispute case step
+    /// is rejected if it references a transaction of another client. Dispute case steps
+    /// referencing unknown transactions are left to the account.
+    pub fn check(&self, activity: &AccountActivity) -> AccountActivityResult<()> {
+        let transaction_id = activity.transaction_id();
+        let client_id = activity.client_id();
+        let owners = self.owners.get(transaction_id)?;
+        match activity {
+            AccountActivity::Deposit(_)
+            | AccountActivity::Withdrawal(_)
+            | AccountActivity::Transfer(_) => match owners {
+                // Duplicates within an account are reported by the account itself
+                Some(owners) if owners.client_id != client_id => {
+                    Err(DuplicateTransaction(transaction_id))
+                }
+                _ => Ok(()),
+            },
+            AccountActivity::Dispute(_)
+            | AccountActivity::Resolve(_)
+            | AccountActivity::Chargeback(_) => match owners {
+                Some(owners) if !owners.contains(client_id) => {
+                    Err(ForeignTransaction { transaction_id, client_id })
+                }
+                _ => Ok(()),
+            },
+            AccountActivity::Freeze(_)
+            | AccountActivity::Unfreeze(_)
+            | AccountActivity::Unlock(_) => Ok(()),
+        }
+    }
+
+    /// Registers a transaction that has been recorded by the accounts of its `owners`, see
+    /// [`Owners::recorded`]. Transactions are only registered once they have been recorded, so
+    /// the registry always agrees with the transaction records of the accounts.
+    ///
+    /// The owners of a transaction that has already been registered are kept.
+    pub fn register(&mut self, transaction_id: TransactionID, owners: Owners) -> StorageResult<()> {
+        if self.owners.get(transaction_id)?.is_none() {
+            self.owners.insert(transaction_id, owners)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Owners, TransactionRegistry};
+    use crate::account::{Account, AccountOptions};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{DuplicateTransaction, ForeignTransaction};
+    use crate::storage::DiskStorage;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+    use std::env;
+    use std::sync::Arc;
+
+    #[test]
+    fn transaction_ids_of_other_clients_are_rejected() {
+        let mut registry = TransactionRegistry::default();
+        let deposit = AccountActivity::deposit(TransactionID(1), ClientID(1), dec!(1.0));
+        registry.check(&deposit).expect("Expected first deposit to be accepted");
+        let owners = Owners::of(&deposit).expect("Expected deposit to issue a transaction");
+        registry.register(TransactionID(1), owners).expect("Expected owners to be registered");
+
+        let result = registry.check(
+            &AccountActivity::withdrawal(TransactionID(1), ClientID(2), dec!(1.0)),
+        );
+        assert_eq!(result, Err(DuplicateTransaction(TransactionID(1))),
+                   "Expected reused transaction ID to be rejected");
+    }
+
+    #[test]
+    fn disputes_must_reference_own_transactions() {
+        let mut registry = TransactionRegistry::default();
+        let transfer =
+            AccountActivity::transfer(TransactionID(1), ClientID(1), ClientID(2), dec!(1));
+        let owners = Owners::of(&transfer).expect("Expected transfer to issue a transaction");
+        registry.register(TransactionID(1), owners).expect("Expected owners to be registered");
+
+        for client_id in [ClientID(1), ClientID(2)] {
+            let result = registry.check(&AccountActivity::dispute(TransactionID(1), client_id));
+            assert_eq!(result, Ok(()), "Expected dispute of client {} to be accepted", client_id);
+        }
+        let result = registry.check(&AccountActivity::chargeback(TransactionID(1), ClientID(3)));
+        assert_eq!(
+            result,
+            Err(ForeignTransaction { transaction_id: TransactionID(1), client_id: ClientID(3) }),
+            "Expected dispute of foreign transaction to be rejected",
+        );
+    }
+
+    #[test]
+    fn registry_holds_transactions_of_existing_accounts() {
+        let mut account = Account::new(ClientID(1));
+        account
+            .transaction(AccountActivity::deposit(TransactionID(7), ClientID(1), dec!(1.0)))
+            .expect("Test setup: deposit failed");
+        let registry = TransactionRegistry::default()
+            .with_accounts([&account])
+            .expect("Test setup: failed to read transaction record");
+
+        let result = registry.check(
+            &AccountActivity::deposit(TransactionID(7), ClientID(2), dec!(1.0)),
+        );
+        assert_eq!(result, Err(DuplicateTransaction(TransactionID(7))),
+                   "Expected transaction ID of existing account to be rejected");
+    }
+
+    #[test]
+    fn registry_holds_owners_in_the_storage_of_the_accounts() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let options = AccountOptions::default().with_storage(Arc::new(storage));
+        let mut account = Account::with_options(ClientID(1), options.clone());
+        account
+            .transaction(AccountActivity::deposit(TransactionID(7), ClientID(1), dec!(1.0)))
+            .expect("Test setup: deposit failed");
+        let registry = TransactionRegistry::with_options(&options)
+            .with_accounts([&account])
+            .expect("Test setup: failed to read transaction record");
+
+        let deposit = AccountActivity::deposit(TransactionID(7), ClientID(2), dec!(1.0));
+        assert_eq!(registry.check(&deposit), Err(DuplicateTransaction(TransactionID(7))),
+                   "Expected transaction ID of existing account to be rejected");
+        let registry = TransactionRegistry::with_options(&options);
+        assert_eq!(registry.check(&deposit), Ok(()),
+                   "Expected new registry to start out empty");
+    }
+}
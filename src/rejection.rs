@@ -0,0 +1,266 @@
+use crate::account_activity::{AccountActivity, AccountActivityError};
+use crate::transaction::TransactionID;
+use crate::ClientID;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use thiserror::Error;
+
+/// A record of the input that has been dropped during processing, either because it could not be
+/// parsed or because the [`AccountActivity`] it describes has been rejected by the account.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Rejection {
+    /// The name of the input the record was read from, if known.
+    source: Option<String>,
+
+    /// The line of the input the record starts on, if known.
+    line: Option<u64>,
+
+    /// The raw content of the record, if known.
+    record: Option<String>,
+
+    /// The kind of the account activity, if the record could be parsed.
+    activity: Option<String>,
+
+    client: Option<ClientID>,
+
+    tx: Option<TransactionID>,
+
+    /// A short, stable identifier of the reason for the rejection.
+    error: &'static str,
+
+    /// A human-readable description of the reason for the rejection.
+    message: String,
+}
+
+impl Rejection {
+    /// The error code used for records that could not be parsed.
+    pub const INVALID_RECORD: &'static str = "invalid_record";
+
+    /// Creates a rejection of a record that could not be parsed.
+    pub fn invalid_record<E: Error>(line: Option<u64>, record: Option<String>, error: &E) -> Self {
+        Self {
+            source: None,
+            line,
+            record,
+            activity: None,
+            client: None,
+            tx: None,
+            error: Self::INVALID_RECORD,
+            message: error.to_string(),
+        }
+    }
+
+    /// Creates a rejection of an account activity that failed to be processed.
+    pub fn failed_activity(
+        line: Option<u64>,
+        record: Option<String>,
+        activity: &AccountActivity,
+        error: &AccountActivityError,
+    ) -> Self {
+        Self {
+            source: None,
+            line,
+            record,
+            activity: Some(activity.to_string()),
+            client: Some(activity.client_id()),
+            tx: Some(activity.transaction_id()),
+            error: error.code(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Sets the name of the input the record was read from.
+    pub fn with_source(mut self, source: Option<&str>) -> Self {
+        self.source = source.map(str::to_string);
+        self
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn line(&self) -> Option<u64> {
+        self.line
+    }
+
+    pub fn record(&self) -> Option<&str> {
+        self.record.as_deref()
+    }
+
+    pub fn client_id(&self) -> Option<ClientID> {
+        self.client
+    }
+
+    pub fn transaction_id(&self) -> Option<TransactionID> {
+        self.tx
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        self.error
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Rejection {
+    /// Formats the location of the record, if known, followed by the reason for the rejection and
+    /// the raw record.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.source, self.line) {
+            (Some(source), Some(line)) => write!(f, "{}:{}: ", source, line)?,
+            (Some(source), None) => write!(f, "{}: ", source)?,
+            (None, Some(line)) => write!(f, "line {}: ", line)?,
+            (None, None) => {}
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(record) = &self.record {
+            write!(f, " (record `{}`)", record)?;
+        }
+        Ok(())
+    }
+}
+
+/// The error processing is aborted with once more records have been rejected than tolerated, see
+/// [`Reporting::with_max_errors`](crate::processor::Reporting::with_max_errors).
+#[derive(Error, Debug)]
+#[error("more than {max_errors} rejected records, aborted at {rejection}")]
+pub struct TooManyRejections {
+    /// The number of rejected records that were tolerated.
+    pub max_errors: u64,
+
+    /// The first rejected record over the limit.
+    pub rejection: Rejection,
+}
+
+/// A destination for [`Rejection`]s emitted during processing.
+pub trait RejectionSink {
+    /// Reports a single rejected record.
+    fn reject(&mut self, rejection: Rejection) -> io::Result<()>;
+
+    /// Flushes any buffered rejections. Called once processing has finished.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`RejectionSink`] that discards all rejections.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiscardRejections;
+
+impl RejectionSink for DiscardRejections {
+    fn reject(&mut self, _rejection: Rejection) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RejectionSink for Vec<Rejection> {
+    fn reject(&mut self, rejection: Rejection) -> io::Result<()> {
+        self.push(rejection);
+        Ok(())
+    }
+}
+
+/// A [`RejectionSink`] that writes rejections as CSV records, including a header line.
+pub struct CsvRejectionWriter<W: io::Write> {
+    writer: csv::Writer<W>,
+}
+
+impl<W: io::Write> CsvRejectionWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: csv::Writer::from_writer(writer) }
+    }
+}
+
+impl<W: io::Write> RejectionSink for CsvRejectionWriter<W> {
+    fn reject(&mut self, rejection: Rejection) -> io::Result<()> {
+        Ok(self.writer.serialize(rejection)?)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A [`RejectionSink`] that writes rejections as JSON objects, one per line.
+pub struct JsonRejectionWriter<W: io::Write> {
+    writer: io::BufWriter<W>,
+}
+
+impl<W: io::Write> JsonRejectionWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: io::BufWriter::new(writer) }
+    }
+}
+
+impl<W: io::Write> RejectionSink for JsonRejectionWriter<W> {
+    fn reject(&mut self, rejection: Rejection) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, &rejection)?;
+        io::Write::write_all(&mut self.writer, b"\n")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(&mut self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account_activity::AccountActivityError::InsufficientFunds;
+    use rust_decimal_macros::dec;
+
+    fn rejection() -> Rejection {
+        Rejection::failed_activity(
+            Some(3),
+            Some("withdrawal,1,2,15.0".into()),
+            &AccountActivity::withdrawal(TransactionID(2), ClientID(1), dec!(15.0)),
+            &InsufficientFunds { requested: dec!(15.0), available: dec!(10.0) },
+        ).with_source(Some("day-1.csv"))
+    }
+
+    fn write<S: RejectionSink>(mut sink: S) {
+        sink.reject(rejection()).expect("Expected rejection to be written");
+        sink.flush().expect("Expected rejections to be flushed");
+    }
+
+    #[test]
+    fn rejections_are_written_as_csv() {
+        let mut output = Vec::new();
+        write(CsvRejectionWriter::new(&mut output));
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        let expected = [
+            "source,line,record,activity,client,tx,error,message",
+            "day-1.csv,3,\"withdrawal,1,2,15.0\",withdrawal,1,2,insufficient_funds,\
+            \"failed transaction: insufficient funds (requested 15.0, available 10.0)\"",
+        ].join("\n");
+        assert_eq!(output.trim(), expected);
+    }
+
+    #[test]
+    fn rejections_are_written_as_json_lines() {
+        let mut output = Vec::new();
+        write(JsonRejectionWriter::new(&mut output));
+        let output = String::from_utf8(output).expect("Failed to convert output into string");
+
+        let expected = concat!(
+            r#"{"source":"day-1.csv","line":3,"record":"withdrawal,1,2,15.0","#,
+            r#""activity":"withdrawal","client":1,"tx":2,"error":"insufficient_funds","#,
+            r#""message":"failed transaction: insufficient funds (requested 15.0, available 10.0)"}"#,
+        );
+        assert_eq!(output.trim(), expected);
+    }
+
+    #[test]
+    fn rejections_are_displayed_with_their_location() {
+        assert_eq!(
+            rejection().to_string(),
+            "day-1.csv:3: failed transaction: insufficient funds (requested 15.0, available 10.0) \
+            (record `withdrawal,1,2,15.0`)",
+        );
+    }
+}
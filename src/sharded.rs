@@ -0,0 +1,866 @@
+//! Parallel processing of account activities.
+//!
+//! Accounts are independent of each other, so activities can be processed concurrently as long as
+//! all activities of a client are processed in order by the same worker. Activities are therefore
+//! routed to a fixed worker thread based on the hash of their [`ClientID`].
+//!
+//! [Transfers](AccountActivity::Transfer) between accounts owned by different workers are split
+//! into their two legs, which are sent to both workers. When reaching the transfer, the worker
+//! owning the destination account checks whether it accepts the transfer and passes the result to
+//! the worker owning the source account, which applies the outgoing leg and reports back whether
+//! the incoming leg is to be applied as well. As every worker processes its activities in input
+//! order, the workers always meet at the earliest pending transfer.
+//!
+//! Transaction IDs are checked against a global [`TransactionRegistry`] before the activities are
+//! routed. The workers report the transactions their accounts have recorded back to the reader,
+//! which registers them. An activity whose check depends on a transaction of another client that
+//! has not been reported back yet waits for the worker applying that transaction.
+//!
+//! Rejections and events are sent to the reader as soon as the workers produce them. The reader
+//! passes them on in processing order through a reorder window: every worker applies its records
+//! in input order, so once a worker has processed all records routed to it up to a position, it
+//! reports nothing before that position anymore. Reports after the earliest position a worker may
+//! still report are held back. If more than [`REORDER_WINDOW`] reports are held back, the reader
+//! stops routing until the lagging workers have caught up, so the reports held in memory are
+//! bounded regardless of the length of the input.
+use crate::account::{Account, AccountOptions, ActivityEffect};
+use crate::account_activity::{AccountActivity, AccountActivityResult, ActivityKind};
+use crate::chronology::{Admitted, Chronological, OutOfOrderPolicy};
+use crate::events::ActivityEvent;
+use crate::processor::{
+    accept_activity, apply_activity, concerned_clients, new_account, reject_activity,
+    reject_record, InputRecord, Reporting,
+};
+use crate::registry::{Owners, TransactionRegistry};
+use crate::rejection::Rejection;
+use crate::summary::Summary;
+use crate::transaction::TransactionID;
+use crate::transfer::Transfer;
+use crate::ClientID;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::mem;
+use std::num::NonZeroUsize;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread;
+
+/// The number of activities that are sent to a worker at once.
+const BATCH_SIZE: usize = 256;
+
+/// The number of batches that may be queued for a worker before the reader blocks.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// The number of reports the reader holds back before it waits for the lagging workers.
+const REORDER_WINDOW: usize = CHANNEL_CAPACITY * BATCH_SIZE;
+
+/// The work a worker performs for a routed record.
+enum Work {
+    /// An activity that only concerns accounts owned by the worker.
+    Activity(AccountActivity),
+
+    /// The outgoing leg of a transfer to an account owned by another worker.
+    TransferOut {
+        transfer: Transfer,
+        check: Receiver<AccountActivityResult<()>>,
+        decision: Sender<bool>,
+    },
+
+    /// The incoming leg of a transfer from an account owned by another worker.
+    TransferIn {
+        transfer: Transfer,
+        check: Sender<AccountActivityResult<()>>,
+        decision: Receiver<bool>,
+    },
+}
+
+/// A transaction of a routed activity as reported back by a worker once the activity has been
+/// applied: its ID and the clients whose accounts have recorded it, if any.
+type Recorded = (TransactionID, Option<Owners>);
+
+/// A [`TransactionRegistry`] that learns about the transactions recorded by the workers after the
+/// activities issuing them have been routed.
+struct PendingRegistry {
+    registry: TransactionRegistry,
+
+    /// The routed transactions that have not been reported back yet, alongside the client that
+    /// issued them and their number.
+    pending: HashMap<TransactionID, (ClientID, usize)>,
+
+    /// The transactions reported back by every worker.
+    recorded: Vec<Receiver<Recorded>>,
+}
+
+impl PendingRegistry {
+    /// Checks `activity` against the registry once all transactions with the same ID of other
+    /// clients have been reported back. The batches of the workers applying these transactions
+    /// are sent first, so the transactions are applied eventually.
+    fn check(
+        &mut self,
+        activity: &AccountActivity,
+        batches: &mut [Vec<Routed>],
+        senders: &[SyncSender<Vec<Routed>>],
+    ) -> io::Result<AccountActivityResult<()>> {
+        let transaction_id = activity.transaction_id();
+        while let Some(&(client_id, _)) = self.pending.get(&transaction_id) {
+            if client_id == activity.client_id() {
+                // Transactions of the same client are applied in order by the same worker
+                break;
+            }
+            let shard = shard_of(client_id, senders.len());
+            if !batches[shard].is_empty() {
+                flush(&mut batches[shard], &senders[shard])?;
+            }
+            let recorded = self.recorded[shard]
+                .recv()
+                .map_err(|_| io::Error::other("shard worker terminated unexpectedly"))?;
+            self.register(recorded)?;
+        }
+        Ok(self.registry.check(activity))
+    }
+
+    /// Marks the transaction issued by a routed activity as pending until it is reported back.
+    fn route(&mut self, activity: &AccountActivity) {
+        if Owners::of(activity).is_some() {
+            let client_id = activity.client_id();
+            self.pending.entry(activity.transaction_id()).or_insert((client_id, 0)).1 += 1;
+        }
+    }
+
+    /// Registers all transactions reported back so far.
+    fn receive(&mut self) -> io::Result<()> {
+        for shard in 0..self.recorded.len() {
+            while let Ok(recorded) = self.recorded[shard].try_recv() {
+                self.register(recorded)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn register(&mut self, (transaction_id, owners): Recorded) -> io::Result<()> {
+        if let Some((_, count)) = self.pending.get_mut(&transaction_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.pending.remove(&transaction_id);
+            }
+        }
+        if let Some(owners) = owners {
+            self.registry.register(transaction_id, owners)?;
+        }
+        Ok(())
+    }
+}
+
+/// Work that has been routed to a worker, alongside the position of its record in the input.
+struct Routed {
+    sequence: usize,
+    source: Option<Arc<str>>,
+    line: Option<u64>,
+    raw: Option<String>,
+    work: Work,
+}
+
+/// A report of the reader or a worker, sent to the reader as soon as it is produced.
+enum Reported {
+    /// The rejection of the record at the given position in the input.
+    Rejected(usize, Rejection),
+
+    /// An event alongside the position of its record and the leg of the activity it describes.
+    Event((usize, usize), ActivityEvent),
+
+    /// The given worker has processed all records routed to it up to the given position.
+    Processed(usize, usize),
+
+    /// The given worker has stopped, whether it has processed all records or not.
+    Stopped(usize),
+}
+
+/// Collects the statistics of the records processed by the reader or a worker and sends their
+/// rejections and events to the reader.
+struct Reports {
+    sender: Sender<Reported>,
+    events: bool,
+    summary: Option<Summary>,
+}
+
+impl Reports {
+    /// Creates an empty collection that holds the reports requested by `reporting`.
+    fn like(reporting: &Reporting, sender: Sender<Reported>) -> Self {
+        Self {
+            sender,
+            events: reporting.has_events(),
+            summary: reporting.has_summary().then(Summary::default),
+        }
+    }
+
+    fn send(&self, reported: Reported) {
+        // The reader only hangs up once it has failed, which is reported by the reader itself
+        let _ = self.sender.send(reported);
+    }
+
+    fn invalid_record(&mut self, sequence: usize, rejection: Rejection) {
+        self.send(Reported::Rejected(sequence, rejection));
+        if let Some(summary) = &mut self.summary {
+            summary.invalid_record();
+        }
+    }
+
+    fn reject(
+        &mut self,
+        sequence: usize,
+        kind: ActivityKind,
+        (rejection, event): (Rejection, ActivityEvent),
+    ) {
+        if let Some(summary) = &mut self.summary {
+            summary.rejected(kind, rejection.error_code());
+        }
+        self.send(Reported::Rejected(sequence, rejection));
+        if self.events {
+            self.send(Reported::Event((sequence, 0), event));
+        }
+    }
+
+    /// Records the events of an activity that has been accepted with `effects`, starting with the
+    /// given leg of the activity. The activity is only counted for its first leg.
+    fn accept(
+        &mut self,
+        (sequence, leg): (usize, usize),
+        effects: &[ActivityEffect],
+        source: Option<&str>,
+        line: Option<u64>,
+        activity: &AccountActivity,
+    ) {
+        if let (Some(summary), Some(effect), 0) = (&mut self.summary, effects.first(), leg) {
+            summary.accepted(activity.kind(), effect.status());
+        }
+        if self.events {
+            for (i, event) in accept_activity(effects, source, line, activity).enumerate() {
+                self.send(Reported::Event((sequence, leg + i), event));
+            }
+        }
+    }
+}
+
+/// Tells the reader that a worker has stopped once dropped, even if the worker panics.
+struct StopSignal {
+    shard: usize,
+    sender: Sender<Reported>,
+}
+
+impl Drop for StopSignal {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Reported::Stopped(self.shard));
+    }
+}
+
+/// Passes the rejections and events of the reader and the workers to [`Reporting`] in processing
+/// order, see the [module documentation](self).
+struct ReorderWindow {
+    reports: Receiver<Reported>,
+
+    /// The last position routed to every worker.
+    routed: Vec<Option<usize>>,
+
+    /// The last position every worker has processed.
+    processed: Vec<Option<usize>>,
+
+    /// The number of workers that have stopped.
+    stopped: usize,
+
+    rejected: BTreeMap<usize, Rejection>,
+    events: BTreeMap<(usize, usize), ActivityEvent>,
+}
+
+impl ReorderWindow {
+    fn new(reports: Receiver<Reported>, shards: usize) -> Self {
+        Self {
+            reports,
+            routed: vec![None; shards],
+            processed: vec![None; shards],
+            stopped: 0,
+            rejected: BTreeMap::new(),
+            events: BTreeMap::new(),
+        }
+    }
+
+    /// Marks the record at `sequence` as routed to the given worker.
+    fn route(&mut self, shard: usize, sequence: usize) {
+        self.routed[shard] = Some(sequence);
+    }
+
+    /// Returns the number of reports held back.
+    fn len(&self) -> usize {
+        self.rejected.len() + self.events.len()
+    }
+
+    fn handle(&mut self, reported: Reported) -> io::Result<()> {
+        match reported {
+            Reported::Rejected(sequence, rejection) => {
+                self.rejected.insert(sequence, rejection);
+            }
+            Reported::Event(position, event) => {
+                self.events.insert(position, event);
+            }
+            Reported::Processed(shard, sequence) => self.processed[shard] = Some(sequence),
+            Reported::Stopped(shard) => {
+                self.stopped += 1;
+                if self.processed[shard] != self.routed[shard] {
+                    return Err(io::Error::other("shard worker terminated unexpectedly"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles all reports received so far.
+    fn receive(&mut self) -> io::Result<()> {
+        while let Ok(reported) = self.reports.try_recv() {
+            self.handle(reported)?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the next report and handles it.
+    fn wait(&mut self) -> io::Result<()> {
+        let reported = self.reports
+            .recv()
+            .map_err(|_| io::Error::other("shard worker terminated unexpectedly"))?;
+        self.handle(reported)
+    }
+
+    /// Passes all reports on that precede the earliest position that may still be reported,
+    /// given that all records before `next` have been routed.
+    fn emit(&mut self, next: usize, reporting: &mut Reporting) -> io::Result<()> {
+        let settled = self.routed
+            .iter()
+            .zip(&self.processed)
+            .map(|(&routed, &processed)| {
+                if processed == routed {
+                    next
+                } else {
+                    processed.map_or(0, |sequence| sequence + 1)
+                }
+            })
+            .min()
+            .unwrap_or(next);
+        while let Some(entry) = self.rejected.first_entry() {
+            if *entry.key() >= settled {
+                break;
+            }
+            reporting.reject(entry.remove())?;
+        }
+        while let Some(entry) = self.events.first_entry() {
+            if entry.key().0 >= settled {
+                break;
+            }
+            let event = entry.remove();
+            if let Some(sink) = reporting.events.as_deref_mut() {
+                sink.emit(event)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits for all workers to stop and passes all remaining reports on.
+    fn finish(mut self, reporting: &mut Reporting) -> io::Result<()> {
+        while self.stopped < self.routed.len() {
+            self.wait()?;
+        }
+        self.emit(usize::MAX, reporting)
+    }
+}
+
+/// The result of a worker: the accounts it owns, the clients whose accounts it created alongside
+/// the position of the creating leg in the input, and the statistics of their activities.
+type ShardResult = (HashMap<ClientID, Account>, Vec<((usize, usize), ClientID)>, Option<Summary>);
+
+/// Like [`process_activities_from`](crate::processor::process_activities_from), but processes
+/// the activities on `shards` worker threads.
+///
+/// Activities are routed to workers over bounded channels by the hash of their client ID, which
+/// preserves the order of the activities of every client. The resulting accounts are returned in
+/// the order in which the first applied activity of each client appeared, preceded by the
+/// existing `accounts`.
+///
+/// Transaction IDs are checked against a global [`TransactionRegistry`], see the
+/// [module documentation](self), and `out_of_order` is applied before the activities are routed.
+/// Rejections and events are passed to `reporting` in processing order while the activities are
+/// processed, holding back at most [`REORDER_WINDOW`] of them.
+pub fn process_activities_sharded<I, R, E>(
+    accounts: Vec<Account>,
+    options: AccountOptions,
+    out_of_order: OutOfOrderPolicy,
+    activities: I,
+    shards: NonZeroUsize,
+    mut reporting: Reporting,
+) -> io::Result<Vec<Account>>
+where
+    E: Error,
+    R: Into<InputRecord<Result<AccountActivity, E>>>,
+    I: Iterator<Item=R>,
+{
+    let shards = shards.get();
+    let registry = TransactionRegistry::with_options(&options).with_accounts(&accounts)?;
+    let mut order = Vec::with_capacity(accounts.len());
+    let mut shard_accounts = (0..shards).map(|_| HashMap::new()).collect::<Vec<_>>();
+    for mut account in accounts {
+        account.configure(options.clone())?;
+        order.push(account.client_id());
+        shard_accounts[shard_of(account.client_id(), shards)].insert(account.client_id(), account);
+    }
+
+    let (mut accounts, summaries, mut created) = thread::scope(|scope| {
+        let (report_sender, reports) = mpsc::channel();
+        let mut recorded = Vec::with_capacity(shards);
+        let (senders, workers): (Vec<_>, Vec<_>) = shard_accounts
+            .into_iter()
+            .enumerate()
+            .map(|(shard, accounts)| {
+                let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+                let (recorded_sender, recorded_receiver) = mpsc::channel();
+                recorded.push(recorded_receiver);
+                let options = options.clone();
+                let reports = Reports::like(&reporting, report_sender.clone());
+                let stop = StopSignal { shard, sender: report_sender.clone() };
+                let worker = move || {
+                    let _stop = stop;
+                    run_shard(shard, accounts, options, reports, receiver, recorded_sender)
+                };
+                (sender, scope.spawn(worker))
+            })
+            .unzip();
+
+        let activities = Chronological::new(activities, out_of_order);
+        let registry = PendingRegistry { registry, pending: HashMap::new(), recorded };
+        let mut window = ReorderWindow::new(reports, shards);
+        let reports = Reports::like(&reporting, report_sender);
+        let routed = route(activities, registry, reports, &mut window, &mut reporting, &senders);
+        drop(senders);
+        let routed = routed.and_then(|reports| {
+            window.finish(&mut reporting)?;
+            Ok(reports)
+        });
+
+        // A failing worker makes the reader fail as well, so its error takes precedence
+        let mut accounts = HashMap::new();
+        let mut created = Vec::new();
+        let mut summaries = Vec::new();
+        for worker in workers {
+            let (shard_accounts, shard_created, summary) = worker
+                .join()
+                .map_err(|_| io::Error::other("shard worker panicked"))??;
+            accounts.extend(shard_accounts);
+            created.extend(shard_created);
+            summaries.extend(summary);
+        }
+        summaries.extend(routed?.summary);
+        io::Result::Ok((accounts, summaries, created))
+    })?;
+
+    for summary in summaries {
+        reporting.merge_summary(summary);
+    }
+    created.sort_by_key(|(position, _)| *position);
+    order.extend(created.into_iter().map(|(_, client_id)| client_id));
+    let accounts = order
+        .into_iter()
+        .filter_map(|client_id| accounts.remove(&client_id))
+        .collect::<Vec<_>>();
+    reporting.finish(&accounts)?;
+    Ok(accounts)
+}
+
+/// Distributes the activities among the workers and reports the records that could not be parsed
+/// or were rejected by the `registry`. The reports of the workers are passed on through the
+/// `window` meanwhile.
+fn route<I, E>(
+    activities: I,
+    mut registry: PendingRegistry,
+    mut reports: Reports,
+    window: &mut ReorderWindow,
+    reporting: &mut Reporting,
+    senders: &[SyncSender<Vec<Routed>>],
+) -> io::Result<Reports>
+where
+    E: Error,
+    I: Iterator<Item=Admitted<E>>,
+{
+    let mut batches: Vec<Vec<Routed>> = senders.iter().map(|_| Vec::new()).collect();
+
+    for (sequence, admitted) in activities.enumerate() {
+        window.receive()?;
+        window.emit(sequence, reporting)?;
+        while window.len() > REORDER_WINDOW {
+            // The lagging workers may be waiting for records that have not been sent yet
+            for (batch, sender) in batches.iter_mut().zip(senders) {
+                if !batch.is_empty() {
+                    flush(batch, sender)?;
+                }
+            }
+            window.wait()?;
+            window.emit(sequence, reporting)?;
+        }
+        let InputRecord { source, line, raw, value } = match admitted {
+            Admitted::Record(record) => record,
+            Admitted::Rejected(InputRecord { source, line, raw, value: activity }, err) => {
+                let rejected = reject_activity(err, source, line, raw, &activity)?;
+                reports.reject(sequence, activity.kind(), rejected);
+                continue;
+            }
+        };
+        let activity = match value {
+            Ok(activity) => activity,
+            Err(err) => {
+                reports.invalid_record(sequence, reject_record(source, line, raw, &err));
+                continue;
+            }
+        };
+        if let Err(err) = registry.check(&activity, &mut batches, senders)? {
+            let rejected = reject_activity(err, source, line, raw, &activity)?;
+            reports.reject(sequence, activity.kind(), rejected);
+            continue;
+        }
+        registry.route(&activity);
+        let shard = shard_of(activity.client_id(), senders.len());
+        let to_shard = match &activity {
+            AccountActivity::Transfer(transfer) => shard_of(transfer.to_client_id(), senders.len()),
+            _ => shard,
+        };
+        let (AccountActivity::Transfer(transfer), true) = (&activity, to_shard != shard) else {
+            let work = Work::Activity(activity);
+            batches[shard].push(Routed { sequence, source, line, raw, work });
+            window.route(shard, sequence);
+            if batches[shard].len() >= BATCH_SIZE {
+                flush(&mut batches[shard], &senders[shard])?;
+                registry.receive()?;
+            }
+            continue;
+        };
+
+        let transfer = *transfer;
+        let (check_sender, check) = mpsc::channel();
+        let (decision, decision_receiver) = mpsc::channel();
+        let work = Work::TransferIn { transfer, check: check_sender, decision: decision_receiver };
+        let routed = Routed { sequence, source: source.clone(), line, raw: None, work };
+        batches[to_shard].push(routed);
+        let work = Work::TransferOut { transfer, check, decision };
+        batches[shard].push(Routed { sequence, source, line, raw, work });
+        window.route(shard, sequence);
+        window.route(to_shard, sequence);
+        // Both legs must reach the workers, otherwise the waiting worker may never be released
+        flush(&mut batches[shard], &senders[shard])?;
+        flush(&mut batches[to_shard], &senders[to_shard])?;
+        registry.receive()?;
+    }
+    for (sender, batch) in senders.iter().zip(batches) {
+        if !batch.is_empty() {
+            send(sender, batch)?;
+        }
+    }
+    Ok(reports)
+}
+
+fn flush(batch: &mut Vec<Routed>, sender: &SyncSender<Vec<Routed>>) -> io::Result<()> {
+    send(sender, mem::replace(batch, Vec::with_capacity(BATCH_SIZE)))
+}
+
+fn send(sender: &SyncSender<Vec<Routed>>, batch: Vec<Routed>) -> io::Result<()> {
+    sender.send(batch).map_err(|_| io::Error::other("shard worker terminated unexpectedly"))
+}
+
+fn shard_of(client_id: ClientID, shards: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    client_id.hash(&mut hasher);
+    (hasher.finish() % shards as u64) as usize
+}
+
+fn run_shard(
+    shard: usize,
+    mut accounts: HashMap<ClientID, Account>,
+    options: AccountOptions,
+    mut reports: Reports,
+    batches: Receiver<Vec<Routed>>,
+    recorded: Sender<Recorded>,
+) -> io::Result<ShardResult> {
+    // The reader only hangs up once it has routed all records, so reports may be dropped
+    let report = |activity: &AccountActivity, owners| {
+        let _ = recorded.send((activity.transaction_id(), owners));
+    };
+    let mut create = |client_id| new_account(client_id, &options);
+    let mut created = Vec::new();
+    for batch in batches {
+        let processed = batch.last().map(|routed| routed.sequence);
+        for Routed { sequence, source, line, raw, work } in batch {
+            let (activity, result) = match work {
+                Work::Activity(activity) => {
+                    let clients = concerned_clients(&activity)
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(_, client_id)| !accounts.contains_key(client_id))
+                        .collect::<Vec<_>>();
+                    let result = apply_activity(&mut accounts, activity.clone(), &mut create);
+                    if Owners::of(&activity).is_some() {
+                        let account = accounts.get(&activity.client_id());
+                        report(&activity, Owners::recorded(&activity, result.is_ok(), account)?);
+                    }
+                    created.extend(clients
+                        .into_iter()
+                        .filter(|(_, client_id)| accounts.contains_key(client_id))
+                        .map(|(leg, client_id)| ((sequence, leg), client_id)));
+                    (activity, result)
+                }
+                Work::TransferOut { transfer, check, decision } => {
+                    let account = accounts.entry(transfer.client_id()).or_insert_with(|| {
+                        created.push(((sequence, 0), transfer.client_id()));
+                        create(transfer.client_id())
+                    });
+                    let activity = AccountActivity::Transfer(transfer);
+                    // The other worker only hangs up if it panicked, which is reported when
+                    // joining it
+                    let Ok(checked) = check.recv() else {
+                        report(&activity, None);
+                        continue;
+                    };
+                    let result = checked.and_then(|()| account.transaction(activity.clone()));
+                    let _ = decision.send(result.is_ok());
+                    report(&activity, Owners::recorded(&activity, result.is_ok(), Some(account))?);
+                    (activity, result.map(|effect| vec![effect]))
+                }
+                Work::TransferIn { transfer, check, decision } => {
+                    let to_client_id = transfer.to_client_id();
+                    let existing = accounts.contains_key(&to_client_id);
+                    let account =
+                        accounts.entry(to_client_id).or_insert_with(|| create(to_client_id));
+                    let _ = check.send(account.check_incoming_transfer(&transfer));
+                    if decision.recv() == Ok(true) {
+                        // The incoming leg has been checked before, so it can only fail to be
+                        // recorded
+                        let effect = account.receive_transfer(transfer).map_err(io::Error::other)?;
+                        let activity = AccountActivity::Transfer(transfer);
+                        let position = (sequence, 1);
+                        reports.accept(position, &[effect], source.as_deref(), line, &activity);
+                        if !existing {
+                            created.push(((sequence, 1), to_client_id));
+                        }
+                    } else if !existing {
+                        // The destination account is only kept if the transfer succeeds
+                        accounts.remove(&to_client_id);
+                    }
+                    continue;
+                }
+            };
+            match result {
+                Ok(effects) => {
+                    reports.accept((sequence, 0), &effects, source.as_deref(), line, &activity);
+                }
+                Err(err) => {
+                    let rejected = reject_activity(err, source, line, raw, &activity)?;
+                    reports.reject(sequence, activity.kind(), rejected);
+                }
+            }
+        }
+        if let Some(sequence) = processed {
+            reports.send(Reported::Processed(shard, sequence));
+        }
+    }
+    Ok((accounts, created, reports.summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{process_activities_sharded, REORDER_WINDOW};
+    use crate::account::{Account, AccountOptions};
+    use crate::account_activity::AccountActivity;
+    use crate::chronology::OutOfOrderPolicy;
+    use crate::processor::{process_activities_from, Reporting};
+    use crate::rejection::{Rejection, RejectionSink};
+    use crate::summary::Summary;
+    use crate::transaction::TransactionID;
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+    use std::cell::Cell;
+    use std::io;
+    use std::num::NonZeroUsize;
+    use std::rc::Rc;
+    use thiserror::Error;
+
+    #[derive(Error, Debug)]
+    #[error("error parsing account activity record")]
+    struct ParseError;
+
+    fn activities() -> Vec<Result<AccountActivity, ParseError>> {
+        let mut activities = Vec::new();
+        for tx in 0..1_000u32 {
+            let client_id = ClientID((tx % 7) as u16);
+            let transaction_id = TransactionID(tx);
+            activities.push(Ok(AccountActivity::deposit(transaction_id, client_id, dec!(10.0))));
+            if tx % 5 == 0 {
+                activities.push(Err(ParseError));
+                activities.push(Ok(AccountActivity::dispute(transaction_id, client_id)));
+            }
+            if tx % 10 == 0 {
+                activities.push(Ok(AccountActivity::resolve(transaction_id, client_id)));
+            }
+            if tx % 15 == 0 {
+                activities.push(Ok(AccountActivity::chargeback(transaction_id, client_id)));
+            }
+            if tx % 13 == 0 {
+                // Reuses the transaction ID of another client
+                let other_client_id = ClientID(((tx + 1) % 7) as u16);
+                activities.push(Ok(AccountActivity::dispute(transaction_id, other_client_id)));
+                activities.push(Ok(AccountActivity::deposit(
+                    transaction_id,
+                    other_client_id,
+                    dec!(1.0),
+                )));
+            }
+            if tx % 3 == 0 {
+                activities.push(Ok(AccountActivity::transfer(
+                    TransactionID(tx + 20_000),
+                    client_id,
+                    ClientID(((tx + 3) % 11) as u16),
+                    dec!(4.0),
+                )));
+            }
+            if tx % 17 == 0 {
+                // Fails for lack of funds and must not create the destination account
+                activities.push(Ok(AccountActivity::transfer(
+                    TransactionID(tx + 30_000),
+                    client_id,
+                    ClientID((20 + tx % 3) as u16),
+                    dec!(1000.0),
+                )));
+            }
+            if tx % 11 == 0 {
+                // Only the transaction IDs of recorded transactions are taken
+                let other_client_id = ClientID(((tx + 2) % 7) as u16);
+                for (transaction_id, activity) in [
+                    (tx + 40_000, AccountActivity::transfer(
+                        TransactionID(tx + 40_000),
+                        client_id,
+                        client_id,
+                        dec!(1.0),
+                    )),
+                    (tx + 50_000, AccountActivity::withdrawal(
+                        TransactionID(tx + 50_000),
+                        client_id,
+                        dec!(1000.0),
+                    )),
+                ] {
+                    activities.push(Ok(activity));
+                    activities.push(Ok(AccountActivity::deposit(
+                        TransactionID(transaction_id),
+                        other_client_id,
+                        dec!(1.0),
+                    )));
+                }
+            }
+            activities.push(Ok(AccountActivity::withdrawal(
+                TransactionID(tx + 10_000),
+                client_id,
+                dec!(15.0),
+            )));
+        }
+        activities
+    }
+
+    fn balances(accounts: &[Account]) -> Vec<(ClientID, String, String, String, bool)> {
+        accounts
+            .iter()
+            .map(|account| (
+                account.client_id(),
+                account.available().to_string(),
+                account.held().to_string(),
+                account.total().to_string(),
+                account.is_locked(),
+            ))
+            .collect()
+    }
+
+    #[test]
+    fn sharded_processing_matches_sequential_processing() {
+        let mut expected_rejections = Vec::new();
+        let mut expected_events = Vec::new();
+        let mut expected_summary = Summary::default();
+        let expected = process_activities_from(
+            Vec::new(),
+            AccountOptions::default(),
+            OutOfOrderPolicy::default(),
+            activities().into_iter(),
+            Reporting::new(&mut expected_rejections)
+                .with_events(&mut expected_events)
+                .with_summary(&mut expected_summary),
+        ).expect("Expected sequential processing to succeed");
+
+        for shards in [1, 2, 3, 8] {
+            let shards = NonZeroUsize::new(shards).expect("Test setup: invalid shard count");
+            let mut rejections = Vec::new();
+            let mut events = Vec::new();
+            let mut summary = Summary::default();
+            let accounts = process_activities_sharded(
+                Vec::new(),
+                AccountOptions::default(),
+                OutOfOrderPolicy::default(),
+                activities().into_iter(),
+                shards,
+                Reporting::new(&mut rejections).with_events(&mut events).with_summary(&mut summary),
+            ).expect("Expected sharded processing to succeed");
+
+            assert_eq!(balances(&accounts), balances(&expected),
+                       "Unexpected balances with {} shards", shards);
+            assert_eq!(rejections, expected_rejections,
+                       "Unexpected rejections with {} shards", shards);
+            assert_eq!(events, expected_events, "Unexpected events with {} shards", shards);
+            assert_eq!(summary, expected_summary, "Unexpected summary with {} shards", shards);
+        }
+    }
+
+    /// Counts the rejections passed to it.
+    struct CountingRejections(Rc<Cell<usize>>);
+
+    impl RejectionSink for CountingRejections {
+        fn reject(&mut self, _: Rejection) -> io::Result<()> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rejections_are_reported_while_processing() {
+        let count = 20_000;
+        let reported = Rc::new(Cell::new(0));
+        let mut rejections = CountingRejections(Rc::clone(&reported));
+        let read = Cell::new(0);
+        let max_held_back = Cell::new(0);
+        let activities = (0..count).flat_map(|tx| {
+            let client_id = ClientID((tx % 4) as u16);
+            [
+                Ok(AccountActivity::deposit(TransactionID(tx), client_id, dec!(1.0))),
+                Err(ParseError),
+            ]
+        });
+        let activities = activities.inspect(|activity| {
+            max_held_back.set(max_held_back.get().max(read.get() - reported.get()));
+            if activity.is_err() {
+                read.set(read.get() + 1);
+            }
+        });
+        let shards = NonZeroUsize::new(4).expect("Test setup: invalid shard count");
+        process_activities_sharded(
+            Vec::new(),
+            AccountOptions::default(),
+            OutOfOrderPolicy::default(),
+            activities,
+            shards,
+            Reporting::new(&mut rejections),
+        ).expect("Expected sharded processing to succeed");
+
+        assert_eq!(reported.get(), count as usize, "Expected all rejections to be reported");
+        assert!(max_held_back.get() <= REORDER_WINDOW + 2,
+                "Expected at most {} rejections to be held back, got {}",
+                REORDER_WINDOW, max_held_back.get());
+    }
+}
@@ -0,0 +1,293 @@
+//! Persistence of the complete account state between runs.
+//!
+//! A snapshot holds everything that is needed to continue processing on top of previously
+//! processed activities: the balances and lock of every account as well as the record of
+//! transactions and the state of their dispute cases. The [ledger](crate::ledger) of an account is
+//! not part of the snapshot.
+use crate::account::{Account, AccountLock, TransactionState};
+use crate::timestamp::Timestamp;
+use crate::transaction::{TransactionID, TransactionKind};
+use crate::ClientID;
+use rust_decimal::Decimal;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::cell::RefCell;
+use std::io;
+use thiserror::Error;
+
+/// The version of the snapshot format written by [`write`].
+pub const VERSION: u32 = 4;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("error accessing snapshot: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unsupported snapshot version: {0} (expected at most {VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+pub type SnapshotResult<T> = Result<T, SnapshotError>;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Snapshot {
+    version: u32,
+    accounts: Vec<AccountSnapshot>,
+}
+
+/// The serialized state of a single [`Account`].
+///
+/// Snapshots are written with an [`AccountWriter`], which streams the transaction record of the
+/// account instead of collecting it into an `AccountSnapshot`.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct AccountSnapshot {
+    pub(crate) client: ClientID,
+    pub(crate) available: Decimal,
+    pub(crate) held: Decimal,
+    pub(crate) total: Decimal,
+    pub(crate) locked: bool,
+
+    /// Snapshots of version 2 and earlier do not record the reason of locks.
+    #[serde(default)]
+    pub(crate) lock: Option<AccountLock>,
+
+    pub(crate) transactions: Vec<TransactionSnapshot>,
+
+    /// The latest point in time of all activities applied to the account. Not recorded by
+    /// snapshots of version 3 and earlier.
+    #[serde(default)]
+    pub(crate) clock: Option<Timestamp>,
+
+    /// The IDs of transactions whose records have been evicted. Not recorded by snapshots of
+    /// version 3 and earlier.
+    #[serde(default)]
+    pub(crate) evicted: Vec<TransactionID>,
+}
+
+/// The serialized state of a recorded transaction.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct TransactionSnapshot {
+    pub(crate) tx: TransactionID,
+
+    /// Snapshots of version 1 do not record the kind of transactions, all of which are treated
+    /// as deposits.
+    #[serde(default)]
+    pub(crate) kind: TransactionKind,
+
+    pub(crate) amount: Decimal,
+    pub(crate) state: TransactionState,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) timestamp: Option<Timestamp>,
+}
+
+/// Serializes an [`Account`] in the format of its [`AccountSnapshot`], streaming the transaction
+/// record from the storage of the account instead of loading it into memory.
+///
+/// Serializers cannot carry the I/O errors of the storage, so a failure of the storage is kept in
+/// `failure` and aborts the serialization.
+pub(crate) struct AccountWriter<'a> {
+    pub(crate) account: &'a Account,
+    pub(crate) failure: &'a RefCell<Option<io::Error>>,
+}
+
+/// Serializes a [`Snapshot`] of accounts, see [`AccountWriter`].
+struct SnapshotWriter<'a> {
+    accounts: &'a [Account],
+    failure: RefCell<Option<io::Error>>,
+}
+
+impl Serialize for SnapshotWriter<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let accounts = self.accounts
+            .iter()
+            .map(|account| AccountWriter { account, failure: &self.failure })
+            .collect::<Vec<_>>();
+        let mut snapshot = serializer.serialize_struct("Snapshot", 2)?;
+        snapshot.serialize_field("version", &VERSION)?;
+        snapshot.serialize_field("accounts", &accounts)?;
+        snapshot.end()
+    }
+}
+
+/// Reads the accounts stored in a snapshot.
+pub fn read<R: io::Read>(reader: R) -> SnapshotResult<Vec<Account>> {
+    let snapshot: Snapshot = serde_json::from_reader(io::BufReader::new(reader))?;
+    if !(1..=VERSION).contains(&snapshot.version) {
+        return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+    }
+    Ok(snapshot.accounts.into_iter().map(Account::from).collect())
+}
+
+/// Writes a snapshot of the given accounts.
+///
+/// The transaction records of the accounts are streamed from their storage, so they are never
+/// held in memory as a whole.
+pub fn write<W: io::Write>(writer: W, accounts: &[Account]) -> SnapshotResult<()> {
+    let snapshot = SnapshotWriter { accounts, failure: RefCell::new(None) };
+    let mut writer = io::BufWriter::new(writer);
+    if let Err(err) = serde_json::to_writer(&mut writer, &snapshot) {
+        return Err(match snapshot.failure.take() {
+            Some(failure) => SnapshotError::Io(failure),
+            None => SnapshotError::Json(err),
+        });
+    }
+    io::Write::flush(&mut writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{AccountOptions, LockCause};
+    use crate::account_activity::AccountActivity;
+    use crate::account_activity::AccountActivityError::{DisputeWindowExpired, NotDisputed};
+    use crate::storage::DiskStorage;
+    use rust_decimal_macros::dec;
+    use std::env;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn accounts() -> Vec<Account> {
+        let client_id = ClientID(1);
+        let account = Account::new(client_id).with_activities([
+            AccountActivity::deposit(TransactionID(1), client_id, dec!(100.0)),
+            AccountActivity::deposit(TransactionID(2), client_id, dec!(50.5)),
+            AccountActivity::withdrawal(TransactionID(3), client_id, dec!(20.0)),
+            AccountActivity::dispute(TransactionID(2), client_id),
+        ]);
+        let locked = Account::new(ClientID(2)).with_activities([
+            AccountActivity::deposit(TransactionID(4), ClientID(2), dec!(10.0)),
+            AccountActivity::dispute(TransactionID(4), ClientID(2)),
+            AccountActivity::chargeback(TransactionID(4), ClientID(2)),
+        ]);
+        vec![account, locked]
+    }
+
+    #[test]
+    fn snapshot_round_trip_restores_accounts() {
+        let accounts = accounts();
+        let mut buffer = Vec::new();
+        write(&mut buffer, &accounts).expect("Expected snapshot to be written");
+
+        let restored = read(buffer.as_slice()).expect("Expected snapshot to be read");
+        assert_eq!(restored, accounts);
+    }
+
+    #[test]
+    fn restored_accounts_continue_dispute_cases() {
+        let mut buffer = Vec::new();
+        write(&mut buffer, &accounts()).expect("Expected snapshot to be written");
+        let mut restored = read(buffer.as_slice()).expect("Expected snapshot to be read");
+        let account = &mut restored[0];
+
+        let result = account.transaction(AccountActivity::resolve(TransactionID(1), ClientID(1)));
+        assert_eq!(result, Err(NotDisputed(TransactionID(1))));
+
+        account
+            .transaction(AccountActivity::resolve(TransactionID(2), ClientID(1)))
+            .expect("Expected resolution of restored dispute case to succeed");
+        assert_eq!(account.available(), dec!(130.5));
+        assert_eq!(account.held(), dec!(0.0));
+
+        let result = account.transaction(AccountActivity::deposit(
+            TransactionID(3),
+            ClientID(1),
+            dec!(1.0),
+        ));
+        assert!(result.is_err(), "Expected restored transaction ID to be rejected as duplicate");
+    }
+
+    #[test]
+    fn evicted_transactions_are_restored() {
+        let options = AccountOptions::default()
+            .with_dispute_window(Duration::from_secs(10))
+            .with_expired_eviction(true);
+        let mut account = Account::with_options(ClientID(1), options.clone());
+        for (tx, timestamp) in [(1, 100), (2, 200)] {
+            let deposit = AccountActivity::deposit(TransactionID(tx), ClientID(1), dec!(1.0));
+            account
+                .transaction(deposit.with_timestamp(Timestamp(timestamp)))
+                .expect("Test setup: deposit failed");
+        }
+        let mut buffer = Vec::new();
+        write(&mut buffer, &[account]).expect("Expected snapshot to be written");
+
+        let mut restored = read(buffer.as_slice()).expect("Expected snapshot to be read");
+        let account = &mut restored[0];
+        account.configure(options).expect("Expected account to be configured");
+        let result = account.transaction(AccountActivity::dispute(TransactionID(1), ClientID(1)));
+        assert_eq!(result, Err(DisputeWindowExpired(TransactionID(1))));
+        let result = account.transaction(AccountActivity::dispute(TransactionID(2), ClientID(1)));
+        assert!(result.is_ok(), "Expected dispute within the restored window to succeed");
+    }
+
+    #[test]
+    fn snapshots_are_streamed_from_disk_storage() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let options = AccountOptions::default()
+            .with_storage(Arc::new(storage))
+            .with_dispute_window(Duration::from_secs(10))
+            .with_expired_eviction(true);
+        let mut accounts = accounts();
+        for account in &mut accounts {
+            account.configure(options.clone()).expect("Test setup: failed to move records");
+        }
+        let deposit = AccountActivity::deposit(TransactionID(5), ClientID(1), dec!(1.0));
+        accounts[0]
+            .transaction(deposit.with_timestamp(Timestamp(100)))
+            .expect("Test setup: deposit failed");
+        let deposit = AccountActivity::deposit(TransactionID(6), ClientID(1), dec!(1.0));
+        accounts[0]
+            .transaction(deposit.with_timestamp(Timestamp(200)))
+            .expect("Test setup: deposit failed");
+
+        let mut buffer = Vec::new();
+        write(&mut buffer, &accounts).expect("Expected snapshot to be written");
+        let restored = read(buffer.as_slice()).expect("Expected snapshot to be read");
+        assert_eq!(restored, accounts, "Expected accounts to be restored from disk storage");
+        assert_eq!(restored[0].transaction_state(TransactionID(5)).ok(),
+                   Some(Some(TransactionState::Evicted)),
+                   "Expected evicted transaction to be restored");
+    }
+
+    #[test]
+    fn version_2_locks_are_restored_as_chargeback_locks() {
+        let input = concat!(
+            r#"{"version":2,"accounts":[{"client":1,"available":"0","held":"0","total":"0","#,
+            r#""locked":true,"transactions":[]}]}"#,
+        );
+        let restored = read(input.as_bytes()).expect("Expected snapshot to be read");
+
+        let lock = restored[0].lock().expect("Expected account to be locked");
+        assert_eq!(lock.cause(), LockCause::Chargeback);
+    }
+
+    #[test]
+    fn version_1_transactions_are_restored_as_deposits() {
+        let input = concat!(
+            r#"{"version":1,"accounts":[{"client":1,"available":"10","held":"0","total":"10","#,
+            r#""locked":false,"transactions":[{"tx":1,"amount":"10","state":"processed"}]}]}"#,
+        );
+        let mut restored = read(input.as_bytes()).expect("Expected snapshot to be read");
+        let account = &mut restored[0];
+
+        account
+            .transaction(AccountActivity::dispute(TransactionID(1), ClientID(1)))
+            .expect("Expected dispute of restored transaction to succeed");
+        assert_eq!(account.held(), dec!(10), "Expected restored transaction to be held");
+        assert_eq!(account.available(), dec!(0));
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let input = r#"{"version":0,"accounts":[]}"#;
+        let result = read(input.as_bytes());
+        assert!(matches!(result, Err(SnapshotError::UnsupportedVersion(0))),
+                "Expected snapshot with unsupported version to be rejected: {:?}", result);
+    }
+}
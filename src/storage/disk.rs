@@ -0,0 +1,603 @@
+use crate::account::{TransactionRecord, TransactionState};
+use crate::registry::Owners;
+use crate::storage::{OwnerStore, RecordVisitor, Storage, StorageResult, TransactionStore};
+use crate::timestamp::Timestamp;
+use crate::transaction::{TransactionID, TransactionKind};
+use crate::ClientID;
+use redb::{Database, Durability, TableDefinition, WriteTransaction};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The records of all accounts, keyed by client and transaction ID.
+const TRANSACTIONS: TableDefinition<u64, &[u8]> = TableDefinition::new("transactions");
+
+/// The IDs of evicted transactions, keyed like [`TRANSACTIONS`]. Their records are removed.
+const EVICTED: TableDefinition<u64, ()> = TableDefinition::new("evicted");
+
+/// The owners of transaction IDs, keyed by owner store and transaction ID.
+const OWNERS: TableDefinition<u64, &[u8]> = TableDefinition::new("owners");
+
+/// The number of pending writes after which they are committed at once.
+///
+/// Every commit is costly, so writes are collected in memory and committed in batches.
+const WRITE_BATCH_SIZE: usize = 1_024;
+
+/// The number of commits after which a commit is made durable.
+///
+/// Commits are not made durable by default, as the database does not outlive the process. The
+/// database can only reuse pages freed by previous commits after a durable commit, though.
+const DURABLE_COMMIT_INTERVAL: usize = 16;
+
+/// The length of an encoded [`TransactionRecord`]: its kind, its state, its amount and its
+/// optional timestamp, preceded by a flag whether it is present.
+const RECORD_LENGTH: usize = 27;
+
+/// The length of encoded [`Owners`]: the issuing client and the optional counterparty, preceded
+/// by a flag whether it is present.
+const OWNERS_LENGTH: usize = 5;
+
+/// Holds the transaction record of every account and the owners of transaction IDs in an
+/// embedded database on disk.
+///
+/// The database is stored in a new file that is removed once the storage and all stores opened
+/// from it have been dropped.
+#[derive(Clone)]
+pub struct DiskStorage {
+    database: Arc<TemporaryDatabase>,
+}
+
+impl DiskStorage {
+    /// Creates a storage backed by a new database file in `dir`.
+    pub fn temporary_in(dir: &Path) -> StorageResult<Self> {
+        static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "payment-processor-{}-{}.redb",
+            process::id(),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        );
+        let path = dir.join(name);
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        let database = Database::builder().create_file(file).map_err(|err| {
+            let _ = fs::remove_file(&path);
+            io_error(err)
+        })?;
+        let database = TemporaryDatabase {
+            database,
+            path,
+            commits: AtomicUsize::new(0),
+            owner_stores: AtomicU32::new(0),
+            pending: Mutex::default(),
+        };
+        // Reading from a table fails unless it has been created before
+        database.write(|transaction| {
+            transaction.open_table(TRANSACTIONS).map_err(io_error)?;
+            transaction.open_table(EVICTED).map_err(io_error)?;
+            transaction.open_table(OWNERS).map_err(io_error)?;
+            Ok(())
+        })?;
+        Ok(Self { database: Arc::new(database) })
+    }
+
+    /// Returns the path of the database file.
+    pub fn path(&self) -> &Path {
+        &self.database.path
+    }
+}
+
+impl Debug for DiskStorage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskStorage").field("path", &self.database.path).finish()
+    }
+}
+
+impl Storage for DiskStorage {
+    fn open(&self, client_id: ClientID) -> Box<dyn TransactionStore> {
+        Box::new(DiskTransactionStore { database: Arc::clone(&self.database), client_id })
+    }
+
+    fn open_owners(&self) -> Box<dyn OwnerStore> {
+        // Every store is given keys of its own, so it starts out empty
+        let store = self.database.owner_stores.fetch_add(1, Ordering::Relaxed);
+        Box::new(DiskOwnerStore { database: Arc::clone(&self.database), store })
+    }
+}
+
+/// A write that has not been committed yet.
+enum PendingWrite {
+    /// An encoded record.
+    Record([u8; RECORD_LENGTH]),
+
+    /// The record has been evicted.
+    Evicted,
+
+    /// The record has been removed.
+    Removed,
+}
+
+/// The writes that have not been committed yet, keyed like the tables.
+#[derive(Default)]
+struct PendingWrites {
+    records: BTreeMap<u64, PendingWrite>,
+    owners: BTreeMap<u64, [u8; OWNERS_LENGTH]>,
+}
+
+/// A database that removes its file when dropped.
+struct TemporaryDatabase {
+    database: Database,
+    path: PathBuf,
+    commits: AtomicUsize,
+
+    /// The number of owner stores opened so far.
+    owner_stores: AtomicU32,
+
+    pending: Mutex<PendingWrites>,
+}
+
+impl TemporaryDatabase {
+    /// Returns the record stored under `key`, including pending writes.
+    fn get(&self, key: u64) -> StorageResult<Option<TransactionRecord>> {
+        // Keys are only written by the store of their client, so a key that is not pending cannot
+        // become pending while it is read from the table
+        match self.pending()?.records.get(&key) {
+            Some(PendingWrite::Record(bytes)) => return decode(bytes).map(Some),
+            Some(PendingWrite::Evicted) => return Ok(Some(TransactionRecord::evicted())),
+            Some(PendingWrite::Removed) => return Ok(None),
+            None => {}
+        }
+        let transaction = self.database.begin_read().map_err(io_error)?;
+        let table = transaction.open_table(TRANSACTIONS).map_err(io_error)?;
+        if let Some(value) = table.get(key).map_err(io_error)? {
+            return decode(value.value()).map(Some);
+        }
+        let evicted = transaction.open_table(EVICTED).map_err(io_error)?;
+        let is_evicted = evicted.get(key).map_err(io_error)?.is_some();
+        Ok(is_evicted.then(TransactionRecord::evicted))
+    }
+
+    /// Returns the owners stored under `key`, including pending writes.
+    fn get_owners(&self, key: u64) -> StorageResult<Option<Owners>> {
+        // Keys are only written by their owner store, see `get`
+        if let Some(bytes) = self.pending()?.owners.get(&key) {
+            return decode_owners(bytes).map(Some);
+        }
+        let transaction = self.database.begin_read().map_err(io_error)?;
+        let table = transaction.open_table(OWNERS).map_err(io_error)?;
+        let value = table.get(key).map_err(io_error)?;
+        value.map(|value| decode_owners(value.value())).transpose()
+    }
+
+    /// Adds a write to the pending writes and commits them once there are enough of them.
+    fn put(&self, key: u64, write: PendingWrite) -> StorageResult<()> {
+        self.stage(|pending| {
+            pending.records.insert(key, write);
+        })
+    }
+
+    /// Adds owners to the pending writes, see [`TemporaryDatabase::put`].
+    fn put_owners(&self, key: u64, owners: Owners) -> StorageResult<()> {
+        self.stage(|pending| {
+            pending.owners.insert(key, encode_owners(owners));
+        })
+    }
+
+    fn stage(&self, f: impl FnOnce(&mut PendingWrites)) -> StorageResult<()> {
+        let mut pending = self.pending()?;
+        f(&mut pending);
+        if pending.records.len() + pending.owners.len() >= WRITE_BATCH_SIZE {
+            self.commit(&mut pending)?;
+        }
+        Ok(())
+    }
+
+    /// Commits all pending writes.
+    fn flush(&self) -> StorageResult<()> {
+        let mut pending = self.pending()?;
+        self.commit(&mut pending)
+    }
+
+    fn commit(&self, pending: &mut PendingWrites) -> StorageResult<()> {
+        if pending.records.is_empty() && pending.owners.is_empty() {
+            return Ok(());
+        }
+        self.write(|transaction| {
+            let mut records = transaction.open_table(TRANSACTIONS).map_err(io_error)?;
+            let mut evicted = transaction.open_table(EVICTED).map_err(io_error)?;
+            for (&key, write) in &pending.records {
+                match write {
+                    PendingWrite::Record(bytes) => {
+                        records.insert(key, bytes.as_slice()).map_err(io_error)?;
+                        evicted.remove(key).map_err(io_error)?;
+                    }
+                    PendingWrite::Evicted => {
+                        records.remove(key).map_err(io_error)?;
+                        evicted.insert(key, ()).map_err(io_error)?;
+                    }
+                    PendingWrite::Removed => {
+                        records.remove(key).map_err(io_error)?;
+                        evicted.remove(key).map_err(io_error)?;
+                    }
+                }
+            }
+            let mut owners = transaction.open_table(OWNERS).map_err(io_error)?;
+            for (&key, bytes) in &pending.owners {
+                owners.insert(key, bytes.as_slice()).map_err(io_error)?;
+            }
+            Ok(())
+        })?;
+        pending.records.clear();
+        pending.owners.clear();
+        Ok(())
+    }
+
+    fn pending(&self) -> StorageResult<MutexGuard<'_, PendingWrites>> {
+        self.pending.lock().map_err(|_| io::Error::other("pending writes are poisoned"))
+    }
+
+    fn write<T, F>(&self, f: F) -> StorageResult<T>
+    where
+        F: FnOnce(&WriteTransaction) -> StorageResult<T>,
+    {
+        let mut transaction = self.database.begin_write().map_err(io_error)?;
+        let commits = self.commits.fetch_add(1, Ordering::Relaxed) + 1;
+        if !commits.is_multiple_of(DURABLE_COMMIT_INTERVAL) {
+            transaction.set_durability(Durability::None);
+        }
+        let value = f(&transaction)?;
+        transaction.commit().map_err(io_error)?;
+        Ok(value)
+    }
+}
+
+impl Drop for TemporaryDatabase {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The transaction record of a single account within a [`DiskStorage`].
+struct DiskTransactionStore {
+    database: Arc<TemporaryDatabase>,
+    client_id: ClientID,
+}
+
+impl DiskTransactionStore {
+    fn key(&self, transaction_id: TransactionID) -> u64 {
+        u64::from(self.client_id.0) << 32 | u64::from(transaction_id.0)
+    }
+
+    /// Returns the range of keys of all transactions of the client.
+    fn keys(&self) -> RangeInclusive<u64> {
+        self.key(TransactionID(u32::MIN))..=self.key(TransactionID(u32::MAX))
+    }
+}
+
+impl Debug for DiskTransactionStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskTransactionStore").field("client_id", &self.client_id).finish()
+    }
+}
+
+impl TransactionStore for DiskTransactionStore {
+    fn get(&self, transaction_id: TransactionID) -> StorageResult<Option<TransactionRecord>> {
+        self.database.get(self.key(transaction_id))
+    }
+
+    fn insert(
+        &mut self,
+        transaction_id: TransactionID,
+        record: TransactionRecord,
+    ) -> StorageResult<bool> {
+        let key = self.key(transaction_id);
+        if self.database.get(key)?.is_some() {
+            return Ok(false);
+        }
+        self.database.put(key, PendingWrite::Record(encode(record)))?;
+        Ok(true)
+    }
+
+    fn update(
+        &mut self,
+        transaction_id: TransactionID,
+        record: TransactionRecord,
+    ) -> StorageResult<()> {
+        self.database.put(self.key(transaction_id), PendingWrite::Record(encode(record)))
+    }
+
+    fn evict(&mut self, transaction_id: TransactionID) -> StorageResult<()> {
+        self.database.put(self.key(transaction_id), PendingWrite::Evicted)
+    }
+
+    fn remove(&mut self, transaction_id: TransactionID) -> StorageResult<()> {
+        self.database.put(self.key(transaction_id), PendingWrite::Removed)
+    }
+
+    fn record_count(&self) -> StorageResult<usize> {
+        self.database.flush()?;
+        let transaction = self.database.database.begin_read().map_err(io_error)?;
+        let table = transaction.open_table(TRANSACTIONS).map_err(io_error)?;
+        let mut count = 0;
+        for entry in table.range(self.keys()).map_err(io_error)? {
+            entry.map_err(io_error)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn for_each_record(&self, visit: &mut RecordVisitor<'_>) -> StorageResult<()> {
+        self.database.flush()?;
+        let transaction = self.database.database.begin_read().map_err(io_error)?;
+        let records = transaction.open_table(TRANSACTIONS).map_err(io_error)?;
+        let evicted = transaction.open_table(EVICTED).map_err(io_error)?;
+        let mut records = records.range(self.keys()).map_err(io_error)?;
+        let mut evicted = evicted.range(self.keys()).map_err(io_error)?;
+        let mut next_record = records.next().transpose().map_err(io_error)?;
+        let mut next_evicted = evicted.next().transpose().map_err(io_error)?;
+        // Both tables are ordered by key and never share a key, so merging them visits the
+        // transactions in order
+        loop {
+            let evicted_first = match (&next_record, &next_evicted) {
+                (Some((record, _)), Some((evicted, _))) => evicted.value() < record.value(),
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (None, None) => return Ok(()),
+            };
+            if evicted_first {
+                if let Some((key, _)) = next_evicted.take() {
+                    visit(transaction_id(key.value()), TransactionRecord::evicted())?;
+                }
+                next_evicted = evicted.next().transpose().map_err(io_error)?;
+            } else {
+                if let Some((key, value)) = next_record.take() {
+                    visit(transaction_id(key.value()), decode(value.value())?)?;
+                }
+                next_record = records.next().transpose().map_err(io_error)?;
+            }
+        }
+    }
+}
+
+/// The owners of transaction IDs within a [`DiskStorage`].
+struct DiskOwnerStore {
+    database: Arc<TemporaryDatabase>,
+    store: u32,
+}
+
+impl DiskOwnerStore {
+    fn key(&self, transaction_id: TransactionID) -> u64 {
+        u64::from(self.store) << 32 | u64::from(transaction_id.0)
+    }
+}
+
+impl Debug for DiskOwnerStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskOwnerStore").field("store", &self.store).finish()
+    }
+}
+
+impl OwnerStore for DiskOwnerStore {
+    fn get(&self, transaction_id: TransactionID) -> StorageResult<Option<Owners>> {
+        self.database.get_owners(self.key(transaction_id))
+    }
+
+    fn insert(&mut self, transaction_id: TransactionID, owners: Owners) -> StorageResult<()> {
+        self.database.put_owners(self.key(transaction_id), owners)
+    }
+}
+
+/// Returns the ID of the transaction stored under `key`, which is held by the lower half of the
+/// key.
+fn transaction_id(key: u64) -> TransactionID {
+    TransactionID(key as u32)
+}
+
+fn io_error(err: impl Into<redb::Error>) -> io::Error {
+    io::Error::other(err.into())
+}
+
+fn encode(record: TransactionRecord) -> [u8; RECORD_LENGTH] {
+    let mut bytes = [0; RECORD_LENGTH];
+    bytes[0] = match record.kind() {
+        TransactionKind::Deposit => 0,
+        TransactionKind::Withdrawal => 1,
+    };
+    bytes[1] = match record.state() {
+        TransactionState::Processed => 0,
+        TransactionState::Disputed => 1,
+        TransactionState::Resolved => 2,
+        TransactionState::ChargedBack => 3,
+        TransactionState::Failed => 4,
+        TransactionState::Evicted => 5,
+    };
+    bytes[2..18].copy_from_slice(&record.amount().serialize());
+    if let Some(timestamp) = record.timestamp() {
+        bytes[18] = 1;
+        bytes[19..].copy_from_slice(&timestamp.0.to_le_bytes());
+    }
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> StorageResult<TransactionRecord> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid transaction record");
+    let (&[kind, state], rest) = bytes.split_first_chunk::<2>().ok_or_else(invalid)?;
+    let (amount, rest) = rest.split_first_chunk::<16>().ok_or_else(invalid)?;
+    let (&[has_timestamp], timestamp) = rest.split_first_chunk::<1>().ok_or_else(invalid)?;
+    let kind = match kind {
+        0 => TransactionKind::Deposit,
+        1 => TransactionKind::Withdrawal,
+        _ => return Err(invalid()),
+    };
+    let state = match state {
+        0 => TransactionState::Processed,
+        1 => TransactionState::Disputed,
+        2 => TransactionState::Resolved,
+        3 => TransactionState::ChargedBack,
+        4 => TransactionState::Failed,
+        5 => TransactionState::Evicted,
+        _ => return Err(invalid()),
+    };
+    let timestamp = match (has_timestamp, timestamp.try_into()) {
+        (0, _) => None,
+        (1, Ok(timestamp)) => Some(Timestamp(u64::from_le_bytes(timestamp))),
+        _ => return Err(invalid()),
+    };
+    Ok(TransactionRecord::new(kind, Decimal::deserialize(*amount))
+        .with_state(state)
+        .with_timestamp(timestamp))
+}
+
+fn encode_owners(owners: Owners) -> [u8; OWNERS_LENGTH] {
+    let mut bytes = [0; OWNERS_LENGTH];
+    bytes[..2].copy_from_slice(&owners.client_id().0.to_le_bytes());
+    if let Some(counterparty) = owners.counterparty() {
+        bytes[2] = 1;
+        bytes[3..].copy_from_slice(&counterparty.0.to_le_bytes());
+    }
+    bytes
+}
+
+fn decode_owners(bytes: &[u8]) -> StorageResult<Owners> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid transaction owners");
+    let &[client_0, client_1, has_counterparty, counterparty_0, counterparty_1] = bytes else {
+        return Err(invalid());
+    };
+    let client_id = ClientID(u16::from_le_bytes([client_0, client_1]));
+    let counterparty = match has_counterparty {
+        0 => None,
+        1 => Some(ClientID(u16::from_le_bytes([counterparty_0, counterparty_1]))),
+        _ => return Err(invalid()),
+    };
+    Ok(Owners::new(client_id, counterparty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiskStorage, WRITE_BATCH_SIZE};
+    use crate::account::{TransactionRecord, TransactionState};
+    use crate::registry::Owners;
+    use crate::storage::{Storage, TransactionStore};
+    use crate::timestamp::Timestamp;
+    use crate::transaction::{TransactionID, TransactionKind};
+    use crate::ClientID;
+    use rust_decimal_macros::dec;
+    use std::env;
+
+    fn records(store: &dyn TransactionStore) -> Vec<(TransactionID, TransactionRecord)> {
+        let mut records = Vec::new();
+        store
+            .for_each_record(&mut |transaction_id, record| {
+                records.push((transaction_id, record));
+                Ok(())
+            })
+            .expect("Expected records to be read");
+        records
+    }
+
+    #[test]
+    fn records_are_stored_per_client() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let mut first = storage.open(ClientID(1));
+        let mut second = storage.open(ClientID(2));
+        let record = TransactionRecord::new(TransactionKind::Withdrawal, dec!(1.2345))
+            .with_timestamp(Some(Timestamp(1_700_000_000)));
+
+        assert_eq!(first.insert(TransactionID(1), record).ok(), Some(true));
+        assert_eq!(first.insert(TransactionID(1), record).ok(), Some(false),
+                   "Expected duplicate to be refused");
+        assert_eq!(second.insert(TransactionID(1), record).ok(), Some(true),
+                   "Expected transaction IDs to be scoped to the client");
+
+        let disputed = record.with_state(TransactionState::Disputed);
+        first.update(TransactionID(1), disputed).expect("Expected record to be updated");
+        assert_eq!(first.get(TransactionID(1)).ok(), Some(Some(disputed)));
+        assert_eq!(records(second.as_ref()), vec![(TransactionID(1), record)]);
+    }
+
+    #[test]
+    fn pending_writes_are_visible_before_and_after_commit() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let mut store = storage.open(ClientID(1));
+        let record = TransactionRecord::new(TransactionKind::Deposit, dec!(1.0));
+        let count = WRITE_BATCH_SIZE as u32 + 10;
+        for tx in 0..count {
+            store.insert(TransactionID(tx), record).expect("Expected record to be inserted");
+        }
+        store.remove(TransactionID(0)).expect("Expected record to be removed");
+
+        assert_eq!(store.get(TransactionID(0)).ok(), Some(None),
+                   "Expected pending removal to be visible");
+        assert_eq!(store.get(TransactionID(1)).ok(), Some(Some(record)),
+                   "Expected committed record to be visible");
+        assert_eq!(store.insert(TransactionID(count - 1), record).ok(), Some(false),
+                   "Expected pending record to be refused as duplicate");
+        assert_eq!(records(store.as_ref()).len(), count as usize - 1);
+    }
+
+    #[test]
+    fn evicted_records_are_removed() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let mut store = storage.open(ClientID(1));
+        let record = TransactionRecord::new(TransactionKind::Deposit, dec!(1.0));
+        for tx in 0..3 {
+            store.insert(TransactionID(tx), record).expect("Expected record to be inserted");
+        }
+        store.evict(TransactionID(1)).expect("Expected record to be evicted");
+
+        assert_eq!(store.get(TransactionID(1)).ok(), Some(Some(TransactionRecord::evicted())),
+                   "Expected pending eviction to be visible");
+        assert_eq!(store.record_count().ok(), Some(2), "Expected evicted record to be removed");
+        assert_eq!(store.get(TransactionID(1)).ok(), Some(Some(TransactionRecord::evicted())),
+                   "Expected committed eviction to be visible");
+        assert_eq!(store.insert(TransactionID(1), record).ok(), Some(false),
+                   "Expected evicted transaction ID to be refused as duplicate");
+        assert_eq!(records(store.as_ref()), vec![
+            (TransactionID(0), record),
+            (TransactionID(1), TransactionRecord::evicted()),
+            (TransactionID(2), record),
+        ]);
+    }
+
+    #[test]
+    fn owners_are_stored_per_owner_store() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let mut owners = storage.open_owners();
+        let transfer = Owners::new(ClientID(1), Some(ClientID(2)));
+        let count = WRITE_BATCH_SIZE as u32 + 10;
+        for tx in 0..count {
+            owners.insert(TransactionID(tx), transfer).expect("Expected owners to be inserted");
+        }
+        let deposit = Owners::new(ClientID(3), None);
+        owners.insert(TransactionID(0), deposit).expect("Expected owners to be replaced");
+
+        assert_eq!(owners.get(TransactionID(0)).ok(), Some(Some(deposit)),
+                   "Expected pending owners to be visible");
+        assert_eq!(owners.get(TransactionID(1)).ok(), Some(Some(transfer)),
+                   "Expected committed owners to be visible");
+        assert_eq!(storage.open_owners().get(TransactionID(1)).ok(), Some(None),
+                   "Expected new owner store to be empty");
+    }
+
+    #[test]
+    fn database_file_is_removed_when_dropped() {
+        let storage = DiskStorage::temporary_in(&env::temp_dir())
+            .expect("Test setup: failed to create storage");
+        let path = storage.path().to_path_buf();
+        let store = storage.open(ClientID(1));
+
+        drop(storage);
+        assert!(path.exists(), "Expected database to be kept while stores are open");
+        drop(store);
+        assert!(!path.exists(), "Expected database to be removed");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::account::{TransactionRecord, TransactionState};
+use crate::registry::Owners;
+use crate::storage::{OwnerStore, RecordVisitor, Storage, StorageResult, TransactionStore};
+use crate::transaction::TransactionID;
+use crate::ClientID;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+/// Holds the transaction record of every account in memory.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryStorage;
+
+impl Storage for MemoryStorage {
+    fn open(&self, _client_id: ClientID) -> Box<dyn TransactionStore> {
+        Box::new(MemoryTransactionStore::default())
+    }
+
+    fn open_owners(&self) -> Box<dyn OwnerStore> {
+        Box::new(MemoryOwnerStore::default())
+    }
+}
+
+/// The owners of transaction IDs held in memory.
+#[derive(Debug, Default)]
+pub struct MemoryOwnerStore {
+    owners: HashMap<TransactionID, Owners>,
+}
+
+impl OwnerStore for MemoryOwnerStore {
+    fn get(&self, transaction_id: TransactionID) -> StorageResult<Option<Owners>> {
+        Ok(self.owners.get(&transaction_id).copied())
+    }
+
+    fn insert(&mut self, transaction_id: TransactionID, owners: Owners) -> StorageResult<()> {
+        self.owners.insert(transaction_id, owners);
+        Ok(())
+    }
+}
+
+/// A transaction record held in memory.
+#[derive(Debug, Default)]
+pub struct MemoryTransactionStore {
+    records: HashMap<TransactionID, TransactionRecord>,
+
+    /// The IDs of evicted transactions, whose records have been removed.
+    evicted: HashSet<TransactionID>,
+}
+
+impl FromIterator<(TransactionID, TransactionRecord)> for MemoryTransactionStore {
+    fn from_iter<I: IntoIterator<Item=(TransactionID, TransactionRecord)>>(records: I) -> Self {
+        let (evicted, records): (Vec<_>, Vec<_>) = records
+            .into_iter()
+            .partition(|(_, record)| record.state() == TransactionState::Evicted);
+        Self {
+            records: records.into_iter().collect(),
+            evicted: evicted.into_iter().map(|(transaction_id, _)| transaction_id).collect(),
+        }
+    }
+}
+
+impl TransactionStore for MemoryTransactionStore {
+    fn get(&self, transaction_id: TransactionID) -> StorageResult<Option<TransactionRecord>> {
+        if self.evicted.contains(&transaction_id) {
+            return Ok(Some(TransactionRecord::evicted()));
+        }
+        Ok(self.records.get(&transaction_id).copied())
+    }
+
+    fn insert(
+        &mut self,
+        transaction_id: TransactionID,
+        record: TransactionRecord,
+    ) -> StorageResult<bool> {
+        if self.evicted.contains(&transaction_id) {
+            return Ok(false);
+        }
+        match self.records.entry(transaction_id) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(record);
+                Ok(true)
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        transaction_id: TransactionID,
+        record: TransactionRecord,
+    ) -> StorageResult<()> {
+        self.evicted.remove(&transaction_id);
+        self.records.insert(transaction_id, record);
+        Ok(())
+    }
+
+    fn evict(&mut self, transaction_id: TransactionID) -> StorageResult<()> {
+        self.records.remove(&transaction_id);
+        self.evicted.insert(transaction_id);
+        Ok(())
+    }
+
+    fn remove(&mut self, transaction_id: TransactionID) -> StorageResult<()> {
+        self.records.remove(&transaction_id);
+        self.evicted.remove(&transaction_id);
+        Ok(())
+    }
+
+    fn record_count(&self) -> StorageResult<usize> {
+        Ok(self.records.len())
+    }
+
+    fn for_each_record(&self, visit: &mut RecordVisitor<'_>) -> StorageResult<()> {
+        let mut transaction_ids = self.records
+            .keys()
+            .chain(self.evicted.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        transaction_ids.sort();
+        for transaction_id in transaction_ids {
+            let record = self.records.get(&transaction_id).copied();
+            visit(transaction_id, record.unwrap_or_else(TransactionRecord::evicted))?;
+        }
+        Ok(())
+    }
+}
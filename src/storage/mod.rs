@@ -0,0 +1,128 @@
+//! Storage of the transaction record of accounts.
+//!
+//! Every [`Account`](crate::account::Account) records its transactions to detect duplicates and to
+//! follow their dispute cases. By default, the record is held in memory, which grows with the
+//! number of processed transactions. A [`Storage`] moves the record elsewhere, e.g. to an embedded
+//! database on disk with [`DiskStorage`], so the memory usage of the accounts does not depend on
+//! the length of the history anymore.
+//!
+//! The same storage holds the owners of every transaction ID for the
+//! [`TransactionRegistry`](crate::registry::TransactionRegistry), which detects transaction IDs
+//! reused across clients.
+use crate::account::TransactionRecord;
+use crate::registry::Owners;
+use crate::transaction::TransactionID;
+use crate::ClientID;
+use std::fmt::Debug;
+use std::io;
+use std::sync::Arc;
+use thiserror::Error;
+
+mod disk;
+mod memory;
+
+pub use disk::DiskStorage;
+pub use memory::{MemoryOwnerStore, MemoryStorage, MemoryTransactionStore};
+
+/// Failures of a storage are reported as I/O errors.
+pub type StorageResult<T> = io::Result<T>;
+
+/// A callback that is called with recorded transactions, see
+/// [`TransactionStore::for_each_record`].
+pub type RecordVisitor<'a> = dyn FnMut(TransactionID, TransactionRecord) -> StorageResult<()> + 'a;
+
+/// A failure of a storage, shared so that errors carrying it can be cloned.
+///
+/// Two failures are equal if they originate from the same I/O error.
+#[derive(Error, Debug, Clone)]
+#[error(transparent)]
+pub struct StorageFailure(Arc<io::Error>);
+
+impl PartialEq for StorageFailure {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl From<io::Error> for StorageFailure {
+    fn from(err: io::Error) -> Self {
+        Self(Arc::new(err))
+    }
+}
+
+impl From<StorageFailure> for io::Error {
+    /// Returns the original I/O error, or an error of the same kind wrapping it if the failure is
+    /// still shared.
+    fn from(failure: StorageFailure) -> Self {
+        Arc::try_unwrap(failure.0)
+            .unwrap_or_else(|shared| io::Error::new(shared.kind(), StorageFailure(shared)))
+    }
+}
+
+/// The transaction record of a single account.
+pub trait TransactionStore: Debug + Send {
+    /// Returns the record of the transaction with the given ID, if it has been recorded.
+    fn get(&self, transaction_id: TransactionID) -> StorageResult<Option<TransactionRecord>>;
+
+    /// Returns whether a transaction with the given ID has been recorded.
+    fn contains(&self, transaction_id: TransactionID) -> StorageResult<bool> {
+        Ok(self.get(transaction_id)?.is_some())
+    }
+
+    /// Records a transaction unless a transaction with the same ID has already been recorded.
+    ///
+    /// Returns whether the transaction has been recorded.
+    fn insert(
+        &mut self,
+        transaction_id: TransactionID,
+        record: TransactionRecord,
+    ) -> StorageResult<bool>;
+
+    /// Replaces the record of a recorded transaction.
+    fn update(
+        &mut self,
+        transaction_id: TransactionID,
+        record: TransactionRecord,
+    ) -> StorageResult<()>;
+
+    /// Removes the record of a transaction and only keeps its ID, so the transaction is
+    /// reported as [evicted](crate::account::TransactionState::Evicted) from then on.
+    fn evict(&mut self, transaction_id: TransactionID) -> StorageResult<()>;
+
+    /// Removes the record of a transaction, if it has been recorded, including its ID.
+    fn remove(&mut self, transaction_id: TransactionID) -> StorageResult<()>;
+
+    /// Returns the number of transactions whose records are held, not counting evicted
+    /// transactions.
+    fn record_count(&self) -> StorageResult<usize>;
+
+    /// Calls `visit` with every recorded transaction in ascending order of their IDs, without
+    /// loading the whole record into memory. Evicted transactions are visited with the
+    /// [tombstone](TransactionRecord::evicted) of their record.
+    ///
+    /// Stops at the first error returned by `visit` and returns it.
+    fn for_each_record(&self, visit: &mut RecordVisitor<'_>) -> StorageResult<()>;
+}
+
+/// The owners of transaction IDs across all clients, see
+/// [`TransactionRegistry`](crate::registry::TransactionRegistry).
+pub trait OwnerStore: Debug + Send {
+    /// Returns the owners of the transaction with the given ID, if it has been registered.
+    fn get(&self, transaction_id: TransactionID) -> StorageResult<Option<Owners>>;
+
+    /// Sets the owners of the transaction with the given ID, replacing any previous owners.
+    fn insert(&mut self, transaction_id: TransactionID, owners: Owners) -> StorageResult<()>;
+}
+
+/// A source of [`TransactionStore`]s, one per client, and of [`OwnerStore`]s.
+pub trait Storage: Debug + Send + Sync {
+    /// Opens the transaction record of the given client.
+    ///
+    /// Opening a store must not fail, failures are reported by the operations of the store.
+    fn open(&self, client_id: ClientID) -> Box<dyn TransactionStore>;
+
+    /// Opens a new, empty store of transaction owners.
+    ///
+    /// Opening a store must not fail, failures are reported by the operations of the store.
+    fn open_owners(&self) -> Box<dyn OwnerStore>;
+}
@@ -0,0 +1,203 @@
+//! Statistics about a processing run.
+//!
+//! A [`Summary`] tallies the records read from the input by the kind of their activity and by
+//! their outcome, counts the reasons records have been rejected for and aggregates the balances of
+//! the resulting accounts.
+use crate::account::{Account, EffectStatus};
+use crate::account_activity::ActivityKind;
+use crate::rejection::Rejection;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The number of activities of a single kind, by outcome.
+#[derive(Serialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ActivityCounts {
+    pub applied: u64,
+
+    /// Dispute case steps referencing unknown transactions.
+    pub ignored: u64,
+
+    pub rejected: u64,
+}
+
+impl ActivityCounts {
+    pub fn total(&self) -> u64 {
+        self.applied + self.ignored + self.rejected
+    }
+}
+
+/// Statistics about the records and accounts of a processing run.
+#[derive(Serialize, Debug, Default, PartialEq, Clone)]
+pub struct Summary {
+    /// The number of records read from the input, including records that could not be parsed.
+    records: u64,
+
+    /// The number of records that could not be parsed.
+    invalid_records: u64,
+
+    /// The number of parsed activities by kind.
+    activities: BTreeMap<&'static str, ActivityCounts>,
+
+    /// The number of rejected records by error code.
+    errors: BTreeMap<&'static str, u64>,
+
+    accounts: u64,
+
+    locked_accounts: u64,
+
+    /// The sum of the available funds of all accounts.
+    available: Decimal,
+
+    /// The sum of the held funds of all accounts.
+    held: Decimal,
+
+    /// The sum of the total funds of all accounts.
+    total: Decimal,
+}
+
+impl Summary {
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    pub fn invalid_records(&self) -> u64 {
+        self.invalid_records
+    }
+
+    /// Returns the number of parsed activities of the given kind.
+    pub fn activities(&self, kind: ActivityKind) -> ActivityCounts {
+        self.activities.get(kind.as_str()).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of records rejected with the given [error code](Rejection::error_code).
+    pub fn errors(&self, code: &str) -> u64 {
+        self.errors.get(code).copied().unwrap_or_default()
+    }
+
+    pub fn accounts(&self) -> u64 {
+        self.accounts
+    }
+
+    pub fn locked_accounts(&self) -> u64 {
+        self.locked_accounts
+    }
+
+    pub fn available(&self) -> Decimal {
+        self.available
+    }
+
+    pub fn held(&self) -> Decimal {
+        self.held
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.total
+    }
+
+    /// Counts a record that could not be parsed.
+    pub(crate) fn invalid_record(&mut self) {
+        self.records += 1;
+        self.invalid_records += 1;
+        *self.errors.entry(Rejection::INVALID_RECORD).or_default() += 1;
+    }
+
+    /// Counts an activity that has been accepted by its account.
+    pub(crate) fn accepted(&mut self, kind: ActivityKind, status: EffectStatus) {
+        self.records += 1;
+        let counts = self.activities.entry(kind.as_str()).or_default();
+        match status {
+            EffectStatus::Applied => counts.applied += 1,
+            EffectStatus::UnknownTransaction => counts.ignored += 1,
+        }
+    }
+
+    /// Counts an activity that has been rejected with the given
+    /// [error code](Rejection::error_code).
+    pub(crate) fn rejected(&mut self, kind: ActivityKind, code: &'static str) {
+        self.records += 1;
+        self.activities.entry(kind.as_str()).or_default().rejected += 1;
+        *self.errors.entry(code).or_default() += 1;
+    }
+
+    /// Adds the counts of another summary, e.g. of another worker.
+    pub(crate) fn merge(&mut self, other: Summary) {
+        self.records += other.records;
+        self.invalid_records += other.invalid_records;
+        for (kind, counts) in other.activities {
+            let merged = self.activities.entry(kind).or_default();
+            merged.applied += counts.applied;
+            merged.ignored += counts.ignored;
+            merged.rejected += counts.rejected;
+        }
+        for (code, count) in other.errors {
+            *self.errors.entry(code).or_default() += count;
+        }
+    }
+
+    /// Sets the account statistics to those of the resulting `accounts`.
+    pub(crate) fn tally_accounts(&mut self, accounts: &[Account]) {
+        self.accounts = accounts.len() as u64;
+        self.locked_accounts = accounts.iter().filter(|account| account.is_locked()).count() as u64;
+        self.available = accounts.iter().map(Account::available).sum();
+        self.held = accounts.iter().map(Account::held).sum();
+        self.total = accounts.iter().map(Account::total).sum();
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "records:         {}", self.records)?;
+        writeln!(f, "invalid records: {}", self.invalid_records)?;
+        writeln!(f, "activities:")?;
+        for (kind, counts) in &self.activities {
+            writeln!(
+                f,
+                "  {:<12} {} (applied {}, ignored {}, rejected {})",
+                kind,
+                counts.total(),
+                counts.applied,
+                counts.ignored,
+                counts.rejected,
+            )?;
+        }
+        writeln!(f, "errors:")?;
+        for (code, count) in &self.errors {
+            writeln!(f, "  {:<27} {}", code, count)?;
+        }
+        writeln!(f, "accounts:        {} ({} locked)", self.accounts, self.locked_accounts)?;
+        writeln!(f, "available:       {}", self.available)?;
+        writeln!(f, "held:            {}", self.held)?;
+        write!(f, "total:           {}", self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Summary;
+    use crate::account::EffectStatus;
+    use crate::account_activity::AccountActivityError::InsufficientFunds;
+    use crate::account_activity::ActivityKind;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn merged_summaries_add_up() {
+        let error = InsufficientFunds { requested: dec!(2.0), available: dec!(1.0) }.code();
+        let mut summary = Summary::default();
+        summary.accepted(ActivityKind::Deposit, EffectStatus::Applied);
+        summary.rejected(ActivityKind::Withdrawal, error);
+        let mut other = Summary::default();
+        other.invalid_record();
+        other.accepted(ActivityKind::Dispute, EffectStatus::UnknownTransaction);
+        other.rejected(ActivityKind::Withdrawal, error);
+
+        summary.merge(other);
+        assert_eq!(summary.records(), 5);
+        assert_eq!(summary.invalid_records(), 1);
+        assert_eq!(summary.activities(ActivityKind::Withdrawal).rejected, 2);
+        assert_eq!(summary.activities(ActivityKind::Dispute).ignored, 1);
+        assert_eq!(summary.errors("insufficient_funds"), 2);
+        assert_eq!(summary.errors("invalid_record"), 1);
+    }
+}
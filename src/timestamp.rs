@@ -0,0 +1,23 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// The point in time an activity took place at, in seconds since the Unix epoch.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash,
+    Default
+)]
+pub struct Timestamp(pub u64);
+
+impl Timestamp {
+    /// Returns the point in time `duration` after this one, saturating at the latest
+    /// representable point in time.
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        Self(self.0.saturating_add(duration.as_secs()))
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -1,9 +1,13 @@
+use crate::timestamp::Timestamp;
 use crate::ClientID;
 use rust_decimal::Decimal;
 use std::fmt::{Display, Formatter};
 
 /// A globally unique transaction ID.
-#[derive(serde::Deserialize, Debug, Eq, PartialEq, Clone, Copy, Hash, Default)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Hash,
+    Default
+)]
 pub struct TransactionID(pub u32);
 
 impl Display for TransactionID {
@@ -12,6 +16,19 @@ impl Display for TransactionID {
     }
 }
 
+/// The kind of a recorded [`Transaction`], which determines how a dispute of the transaction is
+/// accounted for.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    /// Funds were added to the account.
+    #[default]
+    Deposit,
+
+    /// Funds were removed from the account.
+    Withdrawal,
+}
+
 /// A transaction is a financial activity or event where a value is exchanged between two parties.
 /// In the context of finance, it refers to any movement of funds involving accounts, typically 
 /// involving deposits, withdrawals, transfers, or payments.
@@ -23,12 +40,23 @@ pub struct Transaction {
     #[serde(rename = "client")]
     client_id: ClientID,
 
+    #[serde(deserialize_with = "rust_decimal::serde::str::deserialize")]
     amount: Decimal,
+
+    /// The point in time the transaction took place at, if known.
+    #[serde(default)]
+    timestamp: Option<Timestamp>,
 }
 
 impl Transaction {
     pub fn new(id: TransactionID, client_id: ClientID, amount: Decimal) -> Self {
-        Self { id, client_id, amount }
+        Self { id, client_id, amount, timestamp: None }
+    }
+
+    /// Sets the point in time the transaction took place at.
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
     }
 
     pub fn id(&self) -> TransactionID {
@@ -42,4 +70,13 @@ impl Transaction {
     pub fn amount(&self) -> Decimal {
         self.amount
     }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+
+    /// Returns a copy of the transaction with its amount replaced.
+    pub fn with_amount(self, amount: Decimal) -> Self {
+        Self { amount, ..self }
+    }
 }
\ No newline at end of file
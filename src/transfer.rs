@@ -0,0 +1,82 @@
+use crate::timestamp::Timestamp;
+use crate::transaction::{Transaction, TransactionID};
+use crate::ClientID;
+use rust_decimal::Decimal;
+
+/// A transfer moves funds from the account of one client to the account of another client.
+///
+/// A transfer consists of two legs: the outgoing leg withdraws the amount from the source account
+/// and the incoming leg deposits it into the destination account. Both legs are applied
+/// atomically, i.e. either both succeed or neither is applied.
+#[derive(serde::Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct Transfer {
+    #[serde(rename = "tx")]
+    id: TransactionID,
+
+    #[serde(rename = "client")]
+    client_id: ClientID,
+
+    #[serde(rename = "to_client")]
+    to_client_id: ClientID,
+
+    #[serde(deserialize_with = "rust_decimal::serde::str::deserialize")]
+    amount: Decimal,
+
+    /// The point in time the transfer took place at, if known.
+    #[serde(default)]
+    timestamp: Option<Timestamp>,
+}
+
+impl Transfer {
+    pub fn new(
+        id: TransactionID,
+        client_id: ClientID,
+        to_client_id: ClientID,
+        amount: Decimal,
+    ) -> Self {
+        Self { id, client_id, to_client_id, amount, timestamp: None }
+    }
+
+    /// Sets the point in time the transfer took place at.
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn id(&self) -> TransactionID {
+        self.id
+    }
+
+    /// Returns the ID of the client funds are transferred from.
+    pub fn client_id(&self) -> ClientID {
+        self.client_id
+    }
+
+    /// Returns the ID of the client funds are transferred to.
+    pub fn to_client_id(&self) -> ClientID {
+        self.to_client_id
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+
+    /// Returns the transaction the leg of the transfer that concerns the account of `client_id` is
+    /// recorded as.
+    pub fn transaction(&self, client_id: ClientID) -> Transaction {
+        let transaction = Transaction::new(self.id, client_id, self.amount);
+        match self.timestamp {
+            Some(timestamp) => transaction.with_timestamp(timestamp),
+            None => transaction,
+        }
+    }
+
+    /// Returns a copy of the transfer with its amount replaced.
+    pub fn with_amount(self, amount: Decimal) -> Self {
+        Self { amount, ..self }
+    }
+}